@@ -0,0 +1,260 @@
+//! The decoder as it was before the start-byte scan, vendored so that the
+//! benchmark keeps measuring it while the crate changes. It only knows the
+//! replies the crate supported back then, and retries `decode` at every byte
+//! after an error.
+
+use vesc::{CommandReply, DecodeError, Values, ValuesMask};
+
+const CRC16: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
+const FRAME_END: u8 = 3;
+const FRAME_START_SHORT: u8 = 2;
+
+const GET_VALUES: u8 = 4;
+const GET_VALUES_SELECTIVE: u8 = 50;
+
+pub struct Decoder<const BUFLEN: usize = 512> {
+    buf: [u8; BUFLEN],
+    wpos: usize,
+    rpos: usize,
+}
+
+impl<const BUFLEN: usize> Decoder<BUFLEN> {
+    pub fn new() -> Self {
+        Self {
+            buf: [0; BUFLEN],
+            rpos: 0,
+            wpos: 0,
+        }
+    }
+
+    pub fn feed(&mut self, data: &[u8]) -> Result<usize, DecodeError> {
+        if data.len() > self.buf.len().saturating_sub(self.wpos) {
+            self.buf.copy_within(self.rpos..self.wpos, 0);
+            self.wpos = self.wpos.saturating_sub(self.rpos);
+            self.rpos = self.rpos.saturating_sub(self.rpos);
+        }
+
+        if self.wpos == self.buf.len() {
+            self.rpos = 0;
+            self.wpos = 0;
+        }
+
+        let copied = data.len().min(self.buf.len().saturating_sub(self.wpos));
+        self.buf[self.wpos..self.wpos + copied].copy_from_slice(&data[..copied]);
+        self.wpos += copied;
+        Ok(copied)
+    }
+}
+
+impl<const BUFLEN: usize> Iterator for Decoder<BUFLEN> {
+    type Item = CommandReply<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.rpos < self.wpos {
+            match decode(&self.buf[self.rpos..self.wpos]) {
+                Ok((consumed, reply)) => {
+                    self.rpos += consumed;
+                    return Some(reply);
+                }
+                Err(DecodeError::IncompleteData) => return None,
+                _ => (),
+            }
+            self.rpos += 1;
+        }
+        None
+    }
+}
+
+fn decode(buf: &[u8]) -> Result<(usize, CommandReply<'static>), DecodeError> {
+    let mut unpacker = Unpacker::new(buf);
+
+    let frame_start = unpacker.unpack_u8()?;
+    if frame_start != FRAME_START_SHORT {
+        return Err(DecodeError::InvalidFrame);
+    }
+    let payload_len = unpacker.unpack_u8()? as usize;
+    let reply = match unpacker.unpack_u8()? {
+        GET_VALUES => CommandReply::GetValues(unpack_values(&mut unpacker)?),
+        GET_VALUES_SELECTIVE => {
+            CommandReply::GetValuesSelective(unpack_values_selective(&mut unpacker)?)
+        }
+        id => return Err(DecodeError::UnknownPacket { id }),
+    };
+
+    if payload_len != unpacker.pos - (frame_start as usize) {
+        return Err(DecodeError::InvalidFrame);
+    }
+    let payload = &unpacker.buf[(frame_start as usize)..unpacker.pos];
+    let checksum_expected = unpacker.unpack_u16()?;
+    if unpacker.unpack_u8()? != FRAME_END {
+        return Err(DecodeError::InvalidFrame);
+    }
+    let checksum_actual = CRC16.checksum(payload);
+    if checksum_actual != checksum_expected {
+        return Err(DecodeError::ChecksumMismatch {
+            expected: checksum_expected,
+            actual: checksum_actual,
+        });
+    }
+    Ok((unpacker.pos, reply))
+}
+
+fn unpack_values(unpacker: &mut Unpacker) -> Result<Values, DecodeError> {
+    Ok(Values {
+        temp_mosfet: unpacker.unpack_f16(10.0)?,
+        temp_motor: unpacker.unpack_f16(10.0)?,
+        avg_current_motor: unpacker.unpack_f32(100.0)?,
+        avg_current_input: unpacker.unpack_f32(100.0)?,
+        avg_current_d: unpacker.unpack_f32(100.0)?,
+        avg_current_q: unpacker.unpack_f32(100.0)?,
+        duty_cycle: unpacker.unpack_f16(1000.0)?,
+        rpm: unpacker.unpack_f32(1.0)?,
+        voltage_in: unpacker.unpack_f16(10.0)?,
+        amp_hours: unpacker.unpack_f32(10000.0)?,
+        amp_hours_charged: unpacker.unpack_f32(10000.0)?,
+        watt_hours: unpacker.unpack_f32(10000.0)?,
+        watt_hours_charged: unpacker.unpack_f32(10000.0)?,
+        tachometer: unpacker.unpack_i32()?,
+        tachometer_abs: unpacker.unpack_i32()?,
+        fault_code: unpacker.unpack_u8()?,
+        pid_pos: unpacker.unpack_f32(1000000.0)?,
+        controller_id: unpacker.unpack_u8()?,
+        temp_mosfet1: unpacker.unpack_f16(10.0)?,
+        temp_mosfet2: unpacker.unpack_f16(10.0)?,
+        temp_mosfet3: unpacker.unpack_f16(10.0)?,
+        avg_voltage_d: unpacker.unpack_f32(1000.0)?,
+        avg_voltage_q: unpacker.unpack_f32(1000.0)?,
+        status: unpacker.unpack_u8()?,
+    })
+}
+
+fn unpack_values_selective(unpacker: &mut Unpacker) -> Result<Values, DecodeError> {
+    let mut values = Values::default();
+    let mask = ValuesMask::from_bits_retain(unpacker.unpack_u32()?);
+
+    if mask.contains(ValuesMask::TEMP_MOSFET) {
+        values.temp_mosfet = unpacker.unpack_f16(10.0)?;
+    }
+    if mask.contains(ValuesMask::TEMP_MOTOR) {
+        values.temp_motor = unpacker.unpack_f16(10.0)?;
+    }
+    if mask.contains(ValuesMask::AVG_CURRENT_MOTOR) {
+        values.avg_current_motor = unpacker.unpack_f32(100.0)?;
+    }
+    if mask.contains(ValuesMask::AVG_CURRENT_INPUT) {
+        values.avg_current_input = unpacker.unpack_f32(100.0)?;
+    }
+    if mask.contains(ValuesMask::AVG_CURRENT_D) {
+        values.avg_current_d = unpacker.unpack_f32(100.0)?;
+    }
+    if mask.contains(ValuesMask::AVG_CURRENT_Q) {
+        values.avg_current_q = unpacker.unpack_f32(100.0)?;
+    }
+    if mask.contains(ValuesMask::DUTY_CYCLE) {
+        values.duty_cycle = unpacker.unpack_f16(1000.0)?;
+    }
+    if mask.contains(ValuesMask::RPM) {
+        values.rpm = unpacker.unpack_f32(1.0)?;
+    }
+    if mask.contains(ValuesMask::VOLTAGE_IN) {
+        values.voltage_in = unpacker.unpack_f16(10.0)?;
+    }
+    if mask.contains(ValuesMask::AMP_HOURS) {
+        values.amp_hours = unpacker.unpack_f32(10000.0)?;
+    }
+    if mask.contains(ValuesMask::AMP_HOURS_CHARGED) {
+        values.amp_hours_charged = unpacker.unpack_f32(10000.0)?;
+    }
+    if mask.contains(ValuesMask::WATT_HOURS) {
+        values.watt_hours = unpacker.unpack_f32(10000.0)?;
+    }
+    if mask.contains(ValuesMask::WATT_HOURS_CHARGED) {
+        values.watt_hours_charged = unpacker.unpack_f32(10000.0)?;
+    }
+    if mask.contains(ValuesMask::TACHOMETER) {
+        values.tachometer = unpacker.unpack_i32()?;
+    }
+    if mask.contains(ValuesMask::TACHOMETER_ABS) {
+        values.tachometer_abs = unpacker.unpack_i32()?;
+    }
+    if mask.contains(ValuesMask::FAULT_CODE) {
+        values.fault_code = unpacker.unpack_u8()?;
+    }
+    if mask.contains(ValuesMask::PID_POS) {
+        values.pid_pos = unpacker.unpack_f32(1000000.0)?;
+    }
+    if mask.contains(ValuesMask::CONTROLLER_ID) {
+        values.controller_id = unpacker.unpack_u8()?;
+    }
+    if mask.contains(ValuesMask::TEMP_MOSFET_ALL) {
+        values.temp_mosfet1 = unpacker.unpack_f16(10.0)?;
+        values.temp_mosfet2 = unpacker.unpack_f16(10.0)?;
+        values.temp_mosfet3 = unpacker.unpack_f16(10.0)?;
+    }
+    if mask.contains(ValuesMask::AVG_VOLTAGE_D) {
+        values.avg_voltage_d = unpacker.unpack_f32(1000.0)?;
+    }
+    if mask.contains(ValuesMask::AVG_VOLTAGE_Q) {
+        values.avg_voltage_q = unpacker.unpack_f32(1000.0)?;
+    }
+    if mask.contains(ValuesMask::STATUS) {
+        values.status = unpacker.unpack_u8()?;
+    }
+    Ok(values)
+}
+
+struct Unpacker<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Unpacker<'a> {
+    #[inline]
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    #[inline]
+    fn unpack_u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_be_bytes(self.consume(4)?.try_into().unwrap()))
+    }
+
+    #[inline]
+    fn unpack_i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_be_bytes(self.consume(4)?.try_into().unwrap()))
+    }
+
+    #[inline]
+    fn unpack_u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.consume(2)?.try_into().unwrap()))
+    }
+
+    #[inline]
+    fn unpack_i16(&mut self) -> Result<i16, DecodeError> {
+        Ok(i16::from_be_bytes(self.consume(2)?.try_into().unwrap()))
+    }
+
+    #[inline]
+    fn unpack_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(u8::from_be_bytes(self.consume(1)?.try_into().unwrap()))
+    }
+
+    #[inline]
+    fn unpack_f32(&mut self, scale: f32) -> Result<f32, DecodeError> {
+        Ok(self.unpack_i32()? as f32 / scale)
+    }
+
+    #[inline]
+    fn unpack_f16(&mut self, scale: f32) -> Result<f32, DecodeError> {
+        Ok(self.unpack_i16()? as f32 / scale)
+    }
+
+    #[inline]
+    fn consume(&mut self, amount: usize) -> Result<&[u8], DecodeError> {
+        if self.pos + amount > self.buf.len() {
+            return Err(DecodeError::IncompleteData);
+        }
+        self.pos += amount;
+        Ok(&self.buf[self.pos - amount..self.pos])
+    }
+}
@@ -0,0 +1,121 @@
+//! Compares the `Decoder`, fed by copying or written into in place, with the
+//! decoder it replaced, on a clean stream of telemetry and on one with line
+//! noise in between.
+//!
+//! Run with `cargo bench --bench decoder`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use vesc::Decoder;
+
+mod baseline;
+
+// A `GetValues` reply, the bulk of the traffic when polling telemetry.
+const GET_VALUES: [u8; 79] = [
+    2, 74, 4, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+    119, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 1, 116, 0, 0, 0, 0, 255, 255, 131, 64, 0, 2, 168, 254, 0,
+    18, 6, 65, 224, 20, 1, 21, 252, 216, 252, 202, 0, 0, 0, 8, 0, 0, 0, 12, 0, 218, 113, 3,
+];
+
+// A `GetValuesSelective` reply with a handful of fields.
+const GET_VALUES_SELECTIVE: [u8; 28] = [
+    2, 23, 50, 0, 2, 161, 138, 0, 0, 0, 0, 0, 4, 0, 0, 3, 221, 1, 119, 255, 255, 170, 43, 0, 20,
+    45, 58, 3,
+];
+
+const FRAMES: usize = 1000;
+const CHUNK_LEN: usize = 64;
+const ROUNDS: u32 = 50;
+
+fn stream(noise_len: usize) -> Vec<u8> {
+    // A fixed linear congruential generator, so that runs are comparable.
+    let mut state = 0x2545_f491_u32;
+    let mut noise = move || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (state >> 24) as u8
+    };
+
+    let mut stream = Vec::new();
+    for i in 0..FRAMES {
+        if i % 2 == 0 {
+            stream.extend_from_slice(&GET_VALUES);
+        } else {
+            stream.extend_from_slice(&GET_VALUES_SELECTIVE);
+        }
+        stream.extend((0..noise_len).map(|_| noise()));
+    }
+    stream
+}
+
+fn run_write_buf(stream: &[u8]) -> usize {
+    let mut decoder = Decoder::default();
+    let mut decoded = 0;
+    for mut chunk in stream.chunks(CHUNK_LEN) {
+        while !chunk.is_empty() {
+            let free = decoder.write_buf();
+            let written = chunk.len().min(free.len());
+            free[..written].copy_from_slice(&chunk[..written]);
+            decoder.commit(written);
+            chunk = &chunk[written..];
+            decoded += decoder.replies().map(black_box).count();
+        }
+    }
+    decoded
+}
+
+fn run_feed(stream: &[u8]) -> usize {
+    let mut decoder = Decoder::default();
+    let mut decoded = 0;
+    for mut chunk in stream.chunks(CHUNK_LEN) {
+        while !chunk.is_empty() {
+            let fed = decoder.feed(chunk).unwrap();
+            chunk = &chunk[fed..];
+            decoded += decoder.replies().map(black_box).count();
+        }
+    }
+    decoded
+}
+
+fn run_baseline(stream: &[u8]) -> usize {
+    let mut decoder = baseline::Decoder::<512>::new();
+    let mut decoded = 0;
+    for mut chunk in stream.chunks(CHUNK_LEN) {
+        while !chunk.is_empty() {
+            let fed = decoder.feed(chunk).unwrap();
+            chunk = &chunk[fed..];
+            decoded += decoder.by_ref().map(black_box).count();
+        }
+    }
+    decoded
+}
+
+fn bench(name: &str, stream: &[u8], run: fn(&[u8]) -> usize) -> Duration {
+    let decoded = run(stream);
+
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        black_box(run(black_box(stream)));
+        best = best.min(start.elapsed());
+    }
+
+    let ns_per_byte = best.as_nanos() as f64 / stream.len() as f64;
+    let mib_per_s = stream.len() as f64 / best.as_secs_f64() / (1024.0 * 1024.0);
+    println!("{name:<24} {decoded:>5} replies  {ns_per_byte:>7.2} ns/byte  {mib_per_s:>8.1} MiB/s");
+    best
+}
+
+fn main() {
+    for (name, noise_len) in [("clean", 0), ("noisy", 16)] {
+        let stream = stream(noise_len);
+        println!("{name} stream, {} bytes:", stream.len());
+        let feed = bench("  feed", &stream, run_feed);
+        bench("  write_buf and commit", &stream, run_write_buf);
+        let baseline = bench("  baseline (previous)", &stream, run_baseline);
+        println!(
+            "  speedup of feed: {:.2}x\n",
+            baseline.as_secs_f64() / feed.as_secs_f64()
+        );
+    }
+}
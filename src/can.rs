@@ -0,0 +1,743 @@
+//! The native VESC CAN protocol, for hosts that sit directly on the CAN bus
+//! rather than talking to a single controller over UART.
+//!
+//! Every VESC CAN frame uses a 29-bit extended identifier, where the low byte
+//! is the controller ID and the next byte is the packet ID. Commands are
+//! encoded into such frames with [`encode`], and the status messages that
+//! controllers broadcast periodically are decoded with [`decode`]. Regular
+//! commands and their replies, which may not fit into a single frame, are
+//! split with a [`Segmenter`] and put back together with a [`Reassembler`].
+//! A [`CanStatusTable`] keeps the latest status of every controller on the
+//! bus, and a [`LivenessTracker`] detects controllers that went silent.
+//!
+//! With the `embedded-can` feature, frames convert to and from any
+//! `embedded_can::Frame`, so they can be handed to a HAL CAN peripheral
+//! directly. With the `socketcan` feature, `SocketCan` talks to controllers
+//! through a Linux SocketCAN interface.
+//!
+//! # Timestamps
+//!
+//! A [`CanStatusTable`] and a [`LivenessTracker`] do not read a clock. The
+//! caller provides timestamps in any monotonic unit, such as milliseconds
+//! since boot, which are only compared with each other.
+//!
+//! # Example
+//!
+//! ```rust
+//! use vesc::can::{self, Command, Message};
+//!
+//! let frame = can::encode(42, Command::SetRpm(1500)).unwrap();
+//! assert_eq!(frame.id(), 0x032a);
+//! assert_eq!(frame.data(), [0, 0, 5, 220]);
+//!
+//! match can::decode(0x092a, &[0, 0, 5, 220, 0, 15, 1, 244]) {
+//!     Ok((42, Message::Status(status))) => {
+//!         let rpm = status.rpm;
+//!         let current = status.current;
+//!     }
+//!     _ => (),
+//! }
+//! ```
+
+mod liveness;
+#[cfg(all(feature = "socketcan", target_os = "linux"))]
+mod socketcan;
+mod status_table;
+
+use crate::frame::CRC16;
+use crate::packer::{Packer, Unpacker};
+use crate::{CommandReply, DecodeError, EncodeError};
+
+pub use liveness::LivenessTracker;
+#[cfg(all(feature = "socketcan", target_os = "linux"))]
+pub use socketcan::{Received, SocketCan};
+pub use status_table::{CanStatusTable, ControllerStatus};
+
+/// The largest identifier that fits into a 29-bit extended CAN identifier.
+const EXTENDED_ID_MAX: u32 = 0x1fff_ffff;
+
+/// Tells the receiver of a buffered command to process it and send the reply
+/// back to the sender over CAN.
+const PROCESS_AND_REPLY: u8 = 0;
+
+#[repr(u8)]
+enum PacketId {
+    SetDuty = 0,
+    SetCurrent = 1,
+    SetCurrentBrake = 2,
+    SetRpm = 3,
+    SetPos = 4,
+    FillRxBuffer = 5,
+    FillRxBufferLong = 6,
+    ProcessRxBuffer = 7,
+    ProcessShortBuffer = 8,
+    Status = 9,
+    SetCurrentRel = 10,
+    Status2 = 14,
+    Status3 = 15,
+    Status4 = 16,
+    Ping = 17,
+    Pong = 18,
+    Status5 = 27,
+    Status6 = 58,
+}
+
+impl TryFrom<u8> for PacketId {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            id if id == PacketId::SetDuty as u8 => Ok(PacketId::SetDuty),
+            id if id == PacketId::SetCurrent as u8 => Ok(PacketId::SetCurrent),
+            id if id == PacketId::SetCurrentBrake as u8 => Ok(PacketId::SetCurrentBrake),
+            id if id == PacketId::SetRpm as u8 => Ok(PacketId::SetRpm),
+            id if id == PacketId::SetPos as u8 => Ok(PacketId::SetPos),
+            id if id == PacketId::FillRxBuffer as u8 => Ok(PacketId::FillRxBuffer),
+            id if id == PacketId::FillRxBufferLong as u8 => Ok(PacketId::FillRxBufferLong),
+            id if id == PacketId::ProcessRxBuffer as u8 => Ok(PacketId::ProcessRxBuffer),
+            id if id == PacketId::ProcessShortBuffer as u8 => Ok(PacketId::ProcessShortBuffer),
+            id if id == PacketId::Status as u8 => Ok(PacketId::Status),
+            id if id == PacketId::SetCurrentRel as u8 => Ok(PacketId::SetCurrentRel),
+            id if id == PacketId::Status2 as u8 => Ok(PacketId::Status2),
+            id if id == PacketId::Status3 as u8 => Ok(PacketId::Status3),
+            id if id == PacketId::Status4 as u8 => Ok(PacketId::Status4),
+            id if id == PacketId::Ping as u8 => Ok(PacketId::Ping),
+            id if id == PacketId::Pong as u8 => Ok(PacketId::Pong),
+            id if id == PacketId::Status5 as u8 => Ok(PacketId::Status5),
+            id if id == PacketId::Status6 as u8 => Ok(PacketId::Status6),
+            id => Err(DecodeError::UnknownPacket { id }),
+        }
+    }
+}
+
+/// A frame with a 29-bit extended identifier and up to 8 data bytes, as used
+/// by the VESC CAN protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CanFrame {
+    id: u32,
+    len: u8,
+    data: [u8; 8],
+}
+
+impl CanFrame {
+    fn with_parts(packet_id: PacketId, controller_id: u8, header: &[u8], data: &[u8]) -> Self {
+        let len = header.len() + data.len();
+        let mut frame = Self {
+            id: (packet_id as u32) << 8 | controller_id as u32,
+            len: len as u8,
+            data: [0; 8],
+        };
+        frame.data[..header.len()].copy_from_slice(header);
+        frame.data[header.len()..len].copy_from_slice(data);
+        frame
+    }
+
+    /// Creates a frame, or returns `None` if the identifier does not fit into
+    /// 29 bits or there are more than 8 data bytes.
+    pub fn new(id: u32, data: &[u8]) -> Option<Self> {
+        if id > EXTENDED_ID_MAX || data.len() > 8 {
+            return None;
+        }
+        let mut frame = Self {
+            id,
+            len: data.len() as u8,
+            data: [0; 8],
+        };
+        frame.data[..data.len()].copy_from_slice(data);
+        Some(frame)
+    }
+
+    /// The 29-bit extended identifier.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+
+    /// Converts a HAL frame, or returns `None` if it is a remote frame, has a
+    /// standard identifier, or carries more than 8 data bytes.
+    #[cfg(feature = "embedded-can")]
+    pub fn from_frame(frame: &impl embedded_can::Frame) -> Option<Self> {
+        match frame.id() {
+            embedded_can::Id::Extended(id) if frame.is_data_frame() => {
+                Self::new(id.as_raw(), frame.data())
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts into a HAL frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `F` rejects a data frame of 8 bytes or less, which the
+    /// [`embedded_can::Frame`] contract rules out.
+    #[cfg(feature = "embedded-can")]
+    pub fn to_frame<F: embedded_can::Frame>(&self) -> F {
+        let id = embedded_can::ExtendedId::new(self.id).expect("the identifier fits into 29 bits");
+        F::new(id, self.data()).expect("CAN frames accept up to 8 data bytes")
+    }
+}
+
+/// Commands that can be sent to a VESC controller directly over CAN.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Command {
+    /// Sets the duty cycle, from -1.0 to 1.0.
+    SetDuty(f32),
+
+    /// Sets the motor current in amperes.
+    SetCurrent(f32),
+
+    /// Sets the braking current in amperes.
+    SetCurrentBrake(f32),
+
+    /// Sets the motor speed in electrical revolutions per minute (ERPM).
+    SetRpm(i32),
+
+    /// Sets the motor position in degrees.
+    SetPos(f32),
+
+    /// Sets the motor current relative to the configured maximum, from -1.0 to
+    /// 1.0.
+    SetCurrentRel(f32),
+
+    /// Asks the controller to reply with a [`Message::Pong`]. Takes the ID of
+    /// the sending node, which the pong is addressed to.
+    Ping(u8),
+}
+
+impl Command {
+    fn pack_into(&self, packer: &mut Packer) -> Result<PacketId, EncodeError> {
+        Ok(match self {
+            Self::SetDuty(duty) => {
+                packer.pack_f32(*duty, 100_000.0)?;
+                PacketId::SetDuty
+            }
+            Self::SetCurrent(current) => {
+                packer.pack_f32(*current, 1000.0)?;
+                PacketId::SetCurrent
+            }
+            Self::SetCurrentBrake(current) => {
+                packer.pack_f32(*current, 1000.0)?;
+                PacketId::SetCurrentBrake
+            }
+            Self::SetRpm(rpm) => {
+                packer.pack_i32(*rpm)?;
+                PacketId::SetRpm
+            }
+            Self::SetPos(pos) => {
+                packer.pack_f32(*pos, 1_000_000.0)?;
+                PacketId::SetPos
+            }
+            Self::SetCurrentRel(current) => {
+                packer.pack_f32(*current, 100_000.0)?;
+                PacketId::SetCurrentRel
+            }
+            Self::Ping(sender_id) => {
+                packer.pack_u8(*sender_id)?;
+                PacketId::Ping
+            }
+        })
+    }
+}
+
+/// The first status message, with the speed, current and duty cycle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status {
+    pub rpm: f32,
+    pub current: f32,
+    pub duty_cycle: f32,
+}
+
+/// The second status message, with the consumed and regenerated charge.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status2 {
+    pub amp_hours: f32,
+    pub amp_hours_charged: f32,
+}
+
+/// The third status message, with the consumed and regenerated energy.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status3 {
+    pub watt_hours: f32,
+    pub watt_hours_charged: f32,
+}
+
+/// The fourth status message, with temperatures, the input current and the
+/// PID position.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status4 {
+    pub temp_mosfet: f32,
+    pub temp_motor: f32,
+    pub current_input: f32,
+    pub pid_pos: f32,
+}
+
+/// The fifth status message, with the tachometer and input voltage.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status5 {
+    pub tachometer: i32,
+    pub voltage_in: f32,
+}
+
+/// The sixth status message, with the ADC and PPM input levels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status6 {
+    pub adc1: f32,
+    pub adc2: f32,
+    pub adc3: f32,
+    pub ppm: f32,
+}
+
+/// The kind of hardware that answered a ping.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HwType {
+    Vesc,
+    VescBms,
+    CustomModule,
+    Unknown(u8),
+}
+
+impl From<u8> for HwType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Vesc,
+            1 => Self::VescBms,
+            2 => Self::CustomModule,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+/// Messages that VESC controllers send on the CAN bus.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    Status(Status),
+    Status2(Status2),
+    Status3(Status3),
+    Status4(Status4),
+    Status5(Status5),
+    Status6(Status6),
+
+    /// The reply to a [`Command::Ping`].
+    Pong(HwType),
+}
+
+impl Message {
+    fn unpack_from(packet_id: PacketId, unpacker: &mut Unpacker) -> Result<Self, DecodeError> {
+        Ok(match packet_id {
+            PacketId::Status => Self::Status(Status {
+                rpm: unpacker.unpack_f32(1.0)?,
+                current: unpacker.unpack_f16(10.0)?,
+                duty_cycle: unpacker.unpack_f16(1000.0)?,
+            }),
+            PacketId::Status2 => Self::Status2(Status2 {
+                amp_hours: unpacker.unpack_f32(10_000.0)?,
+                amp_hours_charged: unpacker.unpack_f32(10_000.0)?,
+            }),
+            PacketId::Status3 => Self::Status3(Status3 {
+                watt_hours: unpacker.unpack_f32(10_000.0)?,
+                watt_hours_charged: unpacker.unpack_f32(10_000.0)?,
+            }),
+            PacketId::Status4 => Self::Status4(Status4 {
+                temp_mosfet: unpacker.unpack_f16(10.0)?,
+                temp_motor: unpacker.unpack_f16(10.0)?,
+                current_input: unpacker.unpack_f16(10.0)?,
+                pid_pos: unpacker.unpack_f16(50.0)?,
+            }),
+            PacketId::Status5 => {
+                let status = Status5 {
+                    tachometer: unpacker.unpack_i32()?,
+                    voltage_in: unpacker.unpack_f16(10.0)?,
+                };
+                // Reserved by the firmware.
+                unpacker.unpack_i16()?;
+                Self::Status5(status)
+            }
+            PacketId::Status6 => Self::Status6(Status6 {
+                adc1: unpacker.unpack_f16(1000.0)?,
+                adc2: unpacker.unpack_f16(1000.0)?,
+                adc3: unpacker.unpack_f16(1000.0)?,
+                ppm: unpacker.unpack_f16(1000.0)?,
+            }),
+            // Old BMS firmware leaves out the hardware type.
+            PacketId::Pong => match unpacker.unpack_remaining() {
+                [] => Self::Pong(HwType::VescBms),
+                [hw_type] => Self::Pong(HwType::from(*hw_type)),
+                _ => return Err(DecodeError::InvalidFrame),
+            },
+            packet_id => {
+                return Err(DecodeError::UnknownPacket {
+                    id: packet_id as u8,
+                });
+            }
+        })
+    }
+}
+
+/// Encodes a command for the controller with the given ID into a CAN frame.
+pub fn encode(controller_id: u8, command: Command) -> Result<CanFrame, EncodeError> {
+    let mut data = [0u8; 8];
+    let mut packer = Packer::new(&mut data);
+    let packet_id = command.pack_into(&mut packer)?;
+    let len = packer.pos;
+
+    Ok(CanFrame::with_parts(
+        packet_id,
+        controller_id,
+        &[],
+        &data[..len],
+    ))
+}
+
+/// Encodes a command for the controller with the given ID into a HAL frame.
+#[cfg(feature = "embedded-can")]
+pub fn encode_frame<F: embedded_can::Frame>(
+    controller_id: u8,
+    command: Command,
+) -> Result<F, EncodeError> {
+    Ok(encode(controller_id, command)?.to_frame())
+}
+
+/// Decodes a message received on the CAN bus, given the frame's extended
+/// identifier and data. Returns the ID of the controller that sent it along
+/// with the message.
+///
+/// # Errors
+///
+/// Returns [`DecodeError::UnknownPacket`] for frames that do not carry a
+/// status message or pong, and [`DecodeError::InvalidFrame`] if the
+/// identifier is not a VESC identifier or the data does not have the expected
+/// length.
+pub fn decode(id: u32, data: &[u8]) -> Result<(u8, Message), DecodeError> {
+    if id > 0xffff {
+        return Err(DecodeError::InvalidFrame);
+    }
+    let packet_id = PacketId::try_from((id >> 8) as u8)?;
+
+    // Pongs are addressed to the node that sent the ping, and carry the ID of
+    // the controller that sent them in the data instead.
+    let mut unpacker = Unpacker::new(data);
+    let controller_id = match packet_id {
+        PacketId::Pong => unpacker.unpack_u8(),
+        _ => Ok(id as u8),
+    };
+    let unpacked = controller_id.and_then(|controller_id| {
+        Message::unpack_from(packet_id, &mut unpacker).map(|message| (controller_id, message))
+    });
+    match unpacked {
+        Ok(_) if unpacker.pos != data.len() => Err(DecodeError::InvalidFrame),
+        Ok(unpacked) => Ok(unpacked),
+        Err(DecodeError::IncompleteData) => Err(DecodeError::InvalidFrame),
+        Err(err) => Err(err),
+    }
+}
+
+/// Decodes a message received on the CAN bus as a HAL frame, see [`decode`].
+/// Remote frames and frames with a standard identifier are rejected with
+/// [`DecodeError::InvalidFrame`].
+#[cfg(feature = "embedded-can")]
+pub fn decode_frame(frame: &impl embedded_can::Frame) -> Result<(u8, Message), DecodeError> {
+    let frame = CanFrame::from_frame(frame).ok_or(DecodeError::InvalidFrame)?;
+    decode(frame.id(), frame.data())
+}
+
+/// Splits a regular [`Command`](crate::Command) into CAN frames the way the
+/// firmware's `comm_can_send_buffer` does, so that any command can be sent
+/// over CAN.
+///
+/// Payloads of up to 6 bytes fit into a single `PROCESS_SHORT_BUFFER` frame.
+/// Longer payloads are copied into the controller's receive buffer with
+/// `FILL_RX_BUFFER` and `FILL_RX_BUFFER_LONG` frames, followed by a
+/// `PROCESS_RX_BUFFER` frame carrying the length and CRC. The frames must be
+/// sent in the order they are yielded.
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::Command;
+/// use vesc::can::Segmenter;
+///
+/// let mut buf = [0u8; 16];
+/// let segmenter = Segmenter::new(Command::GetValues, 1, 42, &mut buf).unwrap();
+///
+/// for frame in segmenter {
+///     // Transmit `frame.id()` and `frame.data()` as an extended frame.
+/// }
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Segmenter<'a> {
+    payload: &'a [u8],
+    sender_id: u8,
+    controller_id: u8,
+    pos: usize,
+    done: bool,
+}
+
+impl<'a> Segmenter<'a> {
+    /// Creates a segmenter that sends `command` from the node with
+    /// `sender_id` to the controller with `controller_id`, which replies to
+    /// `sender_id`. The command payload is encoded into `buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EncodeError::BufferTooSmall`] if the payload does not fit
+    /// into `buf`, and [`EncodeError::PayloadTooLarge`] if it is longer than
+    /// the protocol allows.
+    pub fn new(
+        command: crate::Command,
+        sender_id: u8,
+        controller_id: u8,
+        buf: &'a mut [u8],
+    ) -> Result<Self, EncodeError> {
+        let mut packer = Packer::new(&mut *buf);
+        command.pack_into(&mut packer)?;
+        let len = packer.pos;
+        if len > u16::MAX as usize {
+            return Err(EncodeError::PayloadTooLarge);
+        }
+
+        let buf: &'a [u8] = buf;
+        Ok(Self {
+            payload: &buf[..len],
+            sender_id,
+            controller_id,
+            pos: 0,
+            done: false,
+        })
+    }
+
+    /// Yields the frames as HAL frames instead, see [`CanFrame::to_frame`].
+    #[cfg(feature = "embedded-can")]
+    pub fn frames<F: embedded_can::Frame>(self) -> impl Iterator<Item = F> + 'a {
+        self.map(|frame| frame.to_frame())
+    }
+}
+
+impl Iterator for Segmenter<'_> {
+    type Item = CanFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.payload.len();
+        let pos = self.pos;
+
+        if self.done {
+            None
+        } else if len <= 6 {
+            self.done = true;
+            let header = [self.sender_id, PROCESS_AND_REPLY];
+            Some(CanFrame::with_parts(
+                PacketId::ProcessShortBuffer,
+                self.controller_id,
+                &header,
+                self.payload,
+            ))
+        } else if pos < len && pos <= u8::MAX as usize {
+            self.pos = len.min(pos + 7);
+            Some(CanFrame::with_parts(
+                PacketId::FillRxBuffer,
+                self.controller_id,
+                &[pos as u8],
+                &self.payload[pos..self.pos],
+            ))
+        } else if pos < len {
+            self.pos = len.min(pos + 6);
+            Some(CanFrame::with_parts(
+                PacketId::FillRxBufferLong,
+                self.controller_id,
+                &(pos as u16).to_be_bytes(),
+                &self.payload[pos..self.pos],
+            ))
+        } else {
+            self.done = true;
+            let [len_hi, len_lo] = (len as u16).to_be_bytes();
+            let [crc_hi, crc_lo] = CRC16.checksum(self.payload).to_be_bytes();
+            let header = [
+                self.sender_id,
+                PROCESS_AND_REPLY,
+                len_hi,
+                len_lo,
+                crc_hi,
+                crc_lo,
+            ];
+            Some(CanFrame::with_parts(
+                PacketId::ProcessRxBuffer,
+                self.controller_id,
+                &header,
+                &[],
+            ))
+        }
+    }
+}
+
+/// Reassembles replies that controllers send over CAN in multiple frames, see
+/// [`Segmenter`] for the format.
+///
+/// Frames are fed one at a time with [`feed`]. Frames that are addressed to
+/// another node or are not part of a buffered transfer are ignored, so all
+/// received frames can be fed. The generic `BUFLEN` bounds the length of a
+/// reply payload.
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::CommandReply;
+/// use vesc::can::Reassembler;
+///
+/// let mut reassembler = Reassembler::<512>::new(1);
+///
+/// let frames: [(u32, &[u8]); 3] = [
+///     (0x0501, &[0, 50, 0, 0, 1, 128, 0, 0]),
+///     (0x0501, &[7, 4, 210]),
+///     (0x0701, &[42, 1, 0, 9, 114, 238]),
+/// ];
+/// for (id, data) in frames {
+///     if let Ok(Some((42, CommandReply::GetValuesSelective(values)))) =
+///         reassembler.feed(id, data)
+///     {
+///         let rpm = values.rpm;
+///     }
+/// }
+/// ```
+///
+/// [`feed`]: Self::feed
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Reassembler<const BUFLEN: usize = 512> {
+    controller_id: u8,
+    buf: [u8; BUFLEN],
+}
+
+impl<const BUFLEN: usize> Reassembler<BUFLEN> {
+    /// Creates a reassembler for replies addressed to the node with
+    /// `controller_id`.
+    pub fn new(controller_id: u8) -> Self {
+        Self {
+            controller_id,
+            buf: [0; BUFLEN],
+        }
+    }
+
+    /// Feeds a received frame, given its extended identifier and data.
+    ///
+    /// Returns the ID of the sending controller along with the reply once a
+    /// transfer is complete, and `None` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecodeError::ChecksumMismatch`] if the reassembled payload is
+    /// corrupted, e.g. because a frame was lost, and
+    /// [`DecodeError::InvalidFrame`] for malformed frames and payloads that
+    /// do not fit into the buffer.
+    pub fn feed(
+        &mut self,
+        id: u32,
+        data: &[u8],
+    ) -> Result<Option<(u8, CommandReply<'_>)>, DecodeError> {
+        if id > 0xffff || id as u8 != self.controller_id {
+            return Ok(None);
+        }
+        let Ok(packet_id) = PacketId::try_from((id >> 8) as u8) else {
+            return Ok(None);
+        };
+
+        // A frame that is too short is malformed rather than incomplete.
+        let transfer = self.receive(packet_id, data).map_err(|err| match err {
+            DecodeError::IncompleteData => DecodeError::InvalidFrame,
+            err => err,
+        })?;
+        let Some((sender_id, payload_len)) = transfer else {
+            return Ok(None);
+        };
+
+        let payload = &self.buf[..payload_len];
+        let mut unpacker = Unpacker::new(payload);
+        match CommandReply::unpack_from(&mut unpacker) {
+            Ok(_) if unpacker.pos != payload_len => Err(DecodeError::InvalidFrame),
+            Ok(reply) => Ok(Some((sender_id, reply))),
+            Err(DecodeError::IncompleteData) => Err(DecodeError::InvalidFrame),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Feeds a received HAL frame, see [`feed`](Self::feed). Frames that are
+    /// not VESC frames are ignored.
+    #[cfg(feature = "embedded-can")]
+    pub fn feed_frame(
+        &mut self,
+        frame: &impl embedded_can::Frame,
+    ) -> Result<Option<(u8, CommandReply<'_>)>, DecodeError> {
+        match CanFrame::from_frame(frame) {
+            Some(frame) => self.feed(frame.id(), frame.data()),
+            None => Ok(None),
+        }
+    }
+
+    /// Handles a frame of a buffered transfer. Returns the sender ID and
+    /// payload length once the payload is complete and intact.
+    fn receive(
+        &mut self,
+        packet_id: PacketId,
+        data: &[u8],
+    ) -> Result<Option<(u8, usize)>, DecodeError> {
+        let mut unpacker = Unpacker::new(data);
+        Ok(Some(match packet_id {
+            PacketId::FillRxBuffer => {
+                let offset = unpacker.unpack_u8()? as usize;
+                self.fill(offset, unpacker.unpack_remaining())?;
+                return Ok(None);
+            }
+            PacketId::FillRxBufferLong => {
+                let offset = unpacker.unpack_u16()? as usize;
+                self.fill(offset, unpacker.unpack_remaining())?;
+                return Ok(None);
+            }
+            PacketId::ProcessRxBuffer => {
+                let sender_id = unpacker.unpack_u8()?;
+                let _commands_send = unpacker.unpack_u8()?;
+                let payload_len = unpacker.unpack_u16()? as usize;
+                let checksum_expected = unpacker.unpack_u16()?;
+                if payload_len > BUFLEN {
+                    return Err(DecodeError::InvalidFrame);
+                }
+                let checksum_actual = CRC16.checksum(&self.buf[..payload_len]);
+                if checksum_actual != checksum_expected {
+                    return Err(DecodeError::ChecksumMismatch {
+                        expected: checksum_expected,
+                        actual: checksum_actual,
+                    });
+                }
+                (sender_id, payload_len)
+            }
+            PacketId::ProcessShortBuffer => {
+                let sender_id = unpacker.unpack_u8()?;
+                let _commands_send = unpacker.unpack_u8()?;
+                let payload = unpacker.unpack_remaining();
+                self.fill(0, payload)?;
+                (sender_id, payload.len())
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    fn fill(&mut self, offset: usize, data: &[u8]) -> Result<(), DecodeError> {
+        self.buf
+            .get_mut(offset..offset + data.len())
+            .ok_or(DecodeError::InvalidFrame)?
+            .copy_from_slice(data);
+        Ok(())
+    }
+}
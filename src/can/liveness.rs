@@ -0,0 +1,122 @@
+use super::{CanFrame, PacketId};
+use crate::DecodeError;
+
+/// Tracks whether a fixed set of `N` controllers is still alive, and flags a
+/// controller as lost once it has been silent for a number of periods.
+///
+/// Any message from a controller counts as a sign of life, be it a status
+/// broadcast or the pong to a ping. Controllers that broadcast their status
+/// are tracked with the status period. Those that do not should be pinged
+/// every period with the frames from [`pings`], in which case a lost
+/// controller is one that missed that many pongs in a row.
+///
+/// Timestamps are provided by the caller, see [Timestamps](super#timestamps).
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::can::LivenessTracker;
+///
+/// // Four wheels broadcasting every 10 ms, lost after 3 missed periods.
+/// let mut tracker = LivenessTracker::new([1, 2, 3, 4], 0, 10, 3);
+///
+/// for id in [0x0901, 0x0902, 0x0903] {
+///     tracker.ingest(25, id, &[0, 0, 5, 220, 0, 15, 1, 244]).unwrap();
+/// }
+///
+/// if tracker.any_lost(30) {
+///     // Stop the vehicle, the fourth wheel went silent.
+/// }
+/// ```
+///
+/// [`pings`]: Self::pings
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LivenessTracker<const N: usize> {
+    controller_ids: [u8; N],
+    last_seen: [u64; N],
+    period: u64,
+    max_missed: u32,
+}
+
+impl<const N: usize> LivenessTracker<N> {
+    /// Creates a tracker for `controller_ids`, which are considered seen at
+    /// `now`. A controller is lost once `max_missed` periods of `period`
+    /// passed without a message from it.
+    pub fn new(controller_ids: [u8; N], now: u64, period: u64, max_missed: u32) -> Self {
+        Self {
+            controller_ids,
+            last_seen: [now; N],
+            period,
+            max_missed,
+        }
+    }
+
+    /// Ingests a frame received at `now`, given its extended identifier and
+    /// data. Returns whether the frame came from a tracked controller, which
+    /// frames of other devices on the bus never do.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecodeError::InvalidFrame`] for malformed messages.
+    pub fn ingest(&mut self, now: u64, id: u32, data: &[u8]) -> Result<bool, DecodeError> {
+        if id > 0xffff {
+            return Ok(false);
+        }
+        match super::decode(id, data) {
+            Ok((controller_id, _)) => Ok(self.seen(now, controller_id)),
+            Err(DecodeError::UnknownPacket { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Records a message from the controller with `controller_id` that has
+    /// already been decoded, e.g. with [`decode`](super::decode). Returns
+    /// whether the controller is tracked.
+    pub fn seen(&mut self, now: u64, controller_id: u8) -> bool {
+        match self
+            .controller_ids
+            .iter()
+            .position(|&id| id == controller_id)
+        {
+            Some(index) => {
+                self.last_seen[index] = self.last_seen[index].max(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the frames that ping every tracked controller on behalf of the
+    /// node with `sender_id`, see [`Command::Ping`](super::Command::Ping).
+    pub fn pings(&self, sender_id: u8) -> impl Iterator<Item = CanFrame> {
+        self.controller_ids.into_iter().map(move |controller_id| {
+            CanFrame::with_parts(PacketId::Ping, controller_id, &[sender_id], &[])
+        })
+    }
+
+    /// Returns when the controller with `controller_id` was last seen, or
+    /// `None` if it is not tracked.
+    pub fn last_seen(&self, controller_id: u8) -> Option<u64> {
+        let index = self
+            .controller_ids
+            .iter()
+            .position(|&id| id == controller_id)?;
+        Some(self.last_seen[index])
+    }
+
+    /// Returns the IDs of the controllers that are lost at `now`.
+    pub fn lost(&self, now: u64) -> impl Iterator<Item = u8> {
+        let timeout = self.period.saturating_mul(self.max_missed as u64);
+        self.controller_ids
+            .into_iter()
+            .zip(self.last_seen)
+            .filter(move |&(_, last_seen)| now.saturating_sub(last_seen) >= timeout)
+            .map(|(controller_id, _)| controller_id)
+    }
+
+    /// Returns whether any controller is lost at `now`.
+    pub fn any_lost(&self, now: u64) -> bool {
+        self.lost(now).next().is_some()
+    }
+}
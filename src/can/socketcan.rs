@@ -0,0 +1,279 @@
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+
+use super::{CanFrame, Command, Message, Reassembler, Segmenter};
+use crate::frame::PAYLOAD_MAX_LEN;
+use crate::{CommandReply, DecodeError};
+
+/// What [`SocketCan::recv`] received from a controller, along with the
+/// controller's ID.
+#[derive(Debug)]
+pub enum Received<'a> {
+    /// A reply to a command sent with [`SocketCan::send`].
+    Reply(u8, CommandReply<'a>),
+
+    /// A status message or pong sent by the controller.
+    Message(u8, Message),
+}
+
+/// A transport that talks to VESC controllers through a Linux SocketCAN
+/// interface, such as `can0` or a virtual `vcan0`.
+///
+/// Regular commands are sent with [`send`] and native CAN commands with
+/// [`send_can`]. Replies and status messages are received with [`recv`].
+/// The socket only lets through frames from the given controllers, so frames
+/// of unrelated devices on the bus never reach the host.
+///
+/// # Example
+///
+/// ```no_run
+/// use vesc::Command;
+/// use vesc::can::{Received, SocketCan};
+///
+/// let mut socket = SocketCan::open("can0", 1, &[42]).unwrap();
+/// socket.send(42, Command::GetValues).unwrap();
+///
+/// loop {
+///     if let Some(Received::Reply(42, reply)) = socket.recv().unwrap() {
+///         println!("{reply:?}");
+///         break;
+///     }
+/// }
+/// ```
+///
+/// [`send`]: Self::send
+/// [`send_can`]: Self::send_can
+/// [`recv`]: Self::recv
+#[derive(Debug)]
+pub struct SocketCan {
+    fd: OwnedFd,
+    node_id: u8,
+    controller_ids: Vec<u8>,
+    reassembler: Reassembler,
+}
+
+impl SocketCan {
+    /// Opens a raw CAN socket on `interface` for the node with `node_id`,
+    /// which controllers send their replies to.
+    ///
+    /// Only status messages and replies from `controller_ids` are received. If
+    /// `controller_ids` is empty, those of all controllers are.
+    pub fn open(interface: &str, node_id: u8, controller_ids: &[u8]) -> io::Result<Self> {
+        let name = CString::new(interface)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        // SAFETY: `name` is a valid NUL-terminated string that outlives the
+        // call.
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: `socket` takes no pointers, and its result is checked below.
+        let fd = unsafe {
+            libc::socket(
+                libc::PF_CAN,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::CAN_RAW,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a freshly opened socket that nothing else owns.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // Status messages carry the sender's ID in the low byte of the
+        // identifier, while buffered replies carry the receiver's ID.
+        let filters: Vec<libc::can_filter> = if controller_ids.is_empty() {
+            vec![filter(libc::CAN_EFF_FLAG, 0)]
+        } else {
+            controller_ids
+                .iter()
+                .chain([&node_id])
+                .map(|&id| filter(libc::CAN_EFF_FLAG | id as u32, 0xff))
+                .collect()
+        };
+        // SAFETY: the pointer and length describe the `filters` slice, which
+        // outlives the call, and `fd` is an open socket.
+        let ret = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_CAN_RAW,
+                libc::CAN_RAW_FILTER,
+                filters.as_ptr().cast(),
+                mem::size_of_val(filters.as_slice()) as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: `sockaddr_can` is a plain C struct, for which all zeroes
+        // is a valid value.
+        let mut addr: libc::sockaddr_can = unsafe { mem::zeroed() };
+        addr.can_family = libc::AF_CAN as libc::sa_family_t;
+        addr.can_ifindex = ifindex as libc::c_int;
+        // SAFETY: the pointer and length describe `addr`, a `sockaddr_can`
+        // that outlives the call, and `fd` is an open socket.
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                (&raw const addr).cast(),
+                mem::size_of::<libc::sockaddr_can>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            fd,
+            node_id,
+            controller_ids: controller_ids.to_vec(),
+            reassembler: Reassembler::new(node_id),
+        })
+    }
+
+    /// Sets how long [`recv`](Self::recv) and [`recv_frame`](Self::recv_frame)
+    /// block before failing with [`io::ErrorKind::WouldBlock`]. `None` blocks
+    /// indefinitely.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] for a zero timeout, which
+    /// the socket would take to mean blocking indefinitely.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        let timeout = timeout.unwrap_or_default();
+        let timeval = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+        // SAFETY: the pointer and length describe `timeval`, which outlives
+        // the call, and `self.fd` is an open socket.
+        let ret = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                (&raw const timeval).cast(),
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Sends a regular command to the controller with `controller_id`, split
+    /// into as many frames as it takes.
+    pub fn send(&self, controller_id: u8, command: crate::Command) -> io::Result<()> {
+        let mut buf = [0u8; PAYLOAD_MAX_LEN];
+        let segmenter = Segmenter::new(command, self.node_id, controller_id, &mut buf)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        for frame in segmenter {
+            self.send_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    /// Sends a native CAN command to the controller with `controller_id`.
+    pub fn send_can(&self, controller_id: u8, command: Command) -> io::Result<()> {
+        let frame = super::encode(controller_id, command)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        self.send_frame(&frame)
+    }
+
+    /// Receives a single frame. Returns a reply once its last frame arrives,
+    /// a status message right away, and `None` for any other frame.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the frame is malformed or
+    /// completes a corrupted reply.
+    pub fn recv(&mut self) -> io::Result<Option<Received<'_>>> {
+        let frame = self.recv_frame()?;
+        let (id, data) = (frame.id(), frame.data());
+
+        // Pongs are addressed to this node, so the sender is only known once
+        // the message is decoded.
+        match super::decode(id, data) {
+            Ok((controller_id, message)) if accepts(&self.controller_ids, controller_id) => {
+                return Ok(Some(Received::Message(controller_id, message)));
+            }
+            Ok(_) => return Ok(None),
+            Err(DecodeError::UnknownPacket { .. }) => (),
+            Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+
+        match self.reassembler.feed(id, data) {
+            Ok(Some((controller_id, reply))) if accepts(&self.controller_ids, controller_id) => {
+                Ok(Some(Received::Reply(controller_id, reply)))
+            }
+            Ok(_) => Ok(None),
+            Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+    }
+
+    /// Sends a raw frame.
+    pub fn send_frame(&self, frame: &CanFrame) -> io::Result<()> {
+        // SAFETY: `can_frame` is a plain C struct, for which all zeroes is a
+        // valid value.
+        let mut raw: libc::can_frame = unsafe { mem::zeroed() };
+        raw.can_id = frame.id() | libc::CAN_EFF_FLAG;
+        raw.can_dlc = frame.data().len() as u8;
+        raw.data[..frame.data().len()].copy_from_slice(frame.data());
+
+        let size = mem::size_of::<libc::can_frame>();
+        // SAFETY: the pointer and length describe `raw`, which outlives the
+        // call, and `self.fd` is an open socket.
+        let ret = unsafe { libc::write(self.fd.as_raw_fd(), (&raw const raw).cast(), size) };
+        match ret {
+            ret if ret < 0 => Err(io::Error::last_os_error()),
+            ret if ret as usize != size => Err(io::ErrorKind::WriteZero.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Receives a raw frame that passed the socket's filter.
+    pub fn recv_frame(&self) -> io::Result<CanFrame> {
+        // SAFETY: `can_frame` is a plain C struct, for which all zeroes is a
+        // valid value.
+        let mut raw: libc::can_frame = unsafe { mem::zeroed() };
+
+        let size = mem::size_of::<libc::can_frame>();
+        // SAFETY: the pointer and length describe `raw`, which is writable
+        // and outlives the call, and `self.fd` is an open socket.
+        let ret = unsafe { libc::read(self.fd.as_raw_fd(), (&raw mut raw).cast(), size) };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if ret as usize != size {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let data = raw.data.get(..raw.can_dlc as usize).unwrap_or(&raw.data);
+        CanFrame::new(raw.can_id & libc::CAN_EFF_MASK, data)
+            .ok_or_else(|| io::ErrorKind::InvalidData.into())
+    }
+}
+
+fn accepts(controller_ids: &[u8], controller_id: u8) -> bool {
+    controller_ids.is_empty() || controller_ids.contains(&controller_id)
+}
+
+fn filter(can_id: u32, id_mask: u32) -> libc::can_filter {
+    libc::can_filter {
+        can_id,
+        can_mask: libc::CAN_EFF_FLAG | libc::CAN_RTR_FLAG | id_mask,
+    }
+}
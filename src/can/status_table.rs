@@ -0,0 +1,162 @@
+use super::{Message, Status, Status2, Status3, Status4, Status5, Status6};
+use crate::DecodeError;
+
+/// The latest status messages received from a single controller.
+///
+/// Controllers can be configured to broadcast any subset of the status
+/// messages, so each one is `None` until it is first received.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ControllerStatus {
+    pub controller_id: u8,
+
+    /// When the last message was received, in the units passed to
+    /// [`CanStatusTable::ingest`].
+    pub last_seen: u64,
+
+    pub status: Option<Status>,
+    pub status_2: Option<Status2>,
+    pub status_3: Option<Status3>,
+    pub status_4: Option<Status4>,
+    pub status_5: Option<Status5>,
+    pub status_6: Option<Status6>,
+}
+
+/// Keeps the latest status of up to `N` controllers on the CAN bus, and
+/// aggregates it across all of them.
+///
+/// Timestamps are provided by the caller, see [Timestamps](super#timestamps).
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::can::CanStatusTable;
+///
+/// let mut table = CanStatusTable::<8>::new();
+/// table.ingest(1000, 0x1b2a, &[0, 0, 0, 0, 1, 192, 0, 0]).unwrap();
+/// table.ingest(1005, 0x1b2b, &[0, 0, 0, 0, 1, 227, 0, 0]).unwrap();
+///
+/// assert_eq!(table.min_voltage_in(), Some(44.8));
+///
+/// // Forget the controllers that did not report within 100 ms.
+/// table.evict_stale(1102, 100);
+/// assert_eq!(table.min_voltage_in(), Some(48.3));
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CanStatusTable<const N: usize> {
+    entries: [Option<ControllerStatus>; N],
+}
+
+impl<const N: usize> Default for CanStatusTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> CanStatusTable<N> {
+    /// Creates an empty table.
+    pub const fn new() -> Self {
+        Self { entries: [None; N] }
+    }
+
+    /// Ingests a frame received at `now`, given its extended identifier and
+    /// data. Returns whether the frame updated the table, which it does not
+    /// for frames that are not status messages, such as those of other
+    /// devices on the bus, or when the table is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecodeError::InvalidFrame`] for malformed status messages.
+    pub fn ingest(&mut self, now: u64, id: u32, data: &[u8]) -> Result<bool, DecodeError> {
+        if id > 0xffff {
+            return Ok(false);
+        }
+        match super::decode(id, data) {
+            Ok((controller_id, message)) => Ok(self.update(now, controller_id, message)),
+            Err(DecodeError::UnknownPacket { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Updates the table with a message that has already been decoded, e.g.
+    /// with [`decode`](super::decode). Returns `false` if the table is full.
+    pub fn update(&mut self, now: u64, controller_id: u8, message: Message) -> bool {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.is_some_and(|entry| entry.controller_id == controller_id))
+            .or_else(|| self.entries.iter().position(Option::is_none));
+        let Some(index) = index else {
+            return false;
+        };
+
+        let entry = self.entries[index].get_or_insert(ControllerStatus {
+            controller_id,
+            last_seen: now,
+            status: None,
+            status_2: None,
+            status_3: None,
+            status_4: None,
+            status_5: None,
+            status_6: None,
+        });
+        entry.last_seen = now;
+        match message {
+            Message::Status(status) => entry.status = Some(status),
+            Message::Status2(status) => entry.status_2 = Some(status),
+            Message::Status3(status) => entry.status_3 = Some(status),
+            Message::Status4(status) => entry.status_4 = Some(status),
+            Message::Status5(status) => entry.status_5 = Some(status),
+            Message::Status6(status) => entry.status_6 = Some(status),
+            Message::Pong(_) => (),
+        }
+        true
+    }
+
+    /// Removes the controllers that were last seen more than `max_age` before
+    /// `now`.
+    pub fn evict_stale(&mut self, now: u64, max_age: u64) {
+        for entry in &mut self.entries {
+            if entry.is_some_and(|entry| now.saturating_sub(entry.last_seen) > max_age) {
+                *entry = None;
+            }
+        }
+    }
+
+    /// Returns the status of the controller with `controller_id`.
+    pub fn get(&self, controller_id: u8) -> Option<&ControllerStatus> {
+        self.iter()
+            .find(|entry| entry.controller_id == controller_id)
+    }
+
+    /// Returns the status of all controllers in the table.
+    pub fn iter(&self) -> impl Iterator<Item = &ControllerStatus> {
+        self.entries.iter().flatten()
+    }
+
+    /// The input current summed over all controllers, in amperes.
+    pub fn total_current_input(&self) -> f32 {
+        self.iter()
+            .filter_map(|entry| entry.status_4)
+            .map(|status| status.current_input)
+            .sum()
+    }
+
+    /// The lowest input voltage of all controllers, in volts.
+    pub fn min_voltage_in(&self) -> Option<f32> {
+        self.iter()
+            .filter_map(|entry| entry.status_5)
+            .map(|status| status.voltage_in)
+            .reduce(f32::min)
+    }
+
+    /// The highest MOSFET or motor temperature of all controllers, in degrees
+    /// Celsius.
+    pub fn max_temperature(&self) -> Option<f32> {
+        self.iter()
+            .filter_map(|entry| entry.status_4)
+            .flat_map(|status| [status.temp_mosfet, status.temp_motor])
+            .reduce(f32::max)
+    }
+}
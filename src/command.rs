@@ -1,10 +1,9 @@
-use bitflags::bitflags;
-
+use super::frame::{
+    CRC16, FRAME_END, FRAME_START_LONG, FRAME_START_SHORT, Frame, PAYLOAD_MAX_LEN, header_len,
+    write_with,
+};
 use super::packer::{Packer, Unpacker};
-
-const CRC16: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
-const FRAME_END: u8 = 3;
-const FRAME_START_SHORT: u8 = 2;
+use super::values::{Values, ValuesMask};
 
 /// Errors that can occur during command encoding.
 #[derive(Debug, PartialEq, Eq, thiserror::Error)]
@@ -13,10 +12,22 @@ const FRAME_START_SHORT: u8 = 2;
 pub enum EncodeError {
     #[error("the output buffer provided for encoding is too small")]
     BufferTooSmall,
+
+    #[error("the command payload does not fit into a single frame")]
+    PayloadTooLarge,
+
+    #[error("the CAN frame has an out-of-range identifier or more than 8 data bytes")]
+    InvalidCanFrame,
+
+    #[error("a value is NaN, infinite or out of range for its fixed-point encoding")]
+    ValueOutOfRange,
+
+    #[error("a string contains a NUL byte, which would end it early")]
+    InvalidString,
 }
 
 /// Errors that can occur during command reply decoding.
-#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 pub enum DecodeError {
@@ -34,13 +45,32 @@ pub enum DecodeError {
 }
 
 #[repr(u8)]
-enum CommandId {
+pub(crate) enum CommandId {
     GetValues = 4,
     SetCurrent = 6,
     SetRpm = 8,
     SetHandbrake = 10,
     ForwardCan = 34,
+    CustomAppData = 36,
     GetValuesSelective = 50,
+    CanFwdFrame = 85,
+    GetCustomConfigXml = 92,
+    GetCustomConfig = 93,
+    GetCustomConfigDefault = 94,
+    SetCustomConfig = 95,
+    LispReadCode = 130,
+    LispWriteCode = 131,
+    LispEraseCode = 132,
+    LispSetRunning = 133,
+    LispGetStats = 134,
+    LispPrint = 135,
+    LispReplCmd = 138,
+    LispStreamCode = 139,
+    FileList = 140,
+    FileRead = 141,
+    FileWrite = 142,
+    FileMkdir = 143,
+    FileRemove = 144,
 }
 
 impl TryFrom<u8> for CommandId {
@@ -53,55 +83,33 @@ impl TryFrom<u8> for CommandId {
             id if id == CommandId::SetRpm as u8 => Ok(CommandId::SetRpm),
             id if id == CommandId::SetHandbrake as u8 => Ok(CommandId::SetHandbrake),
             id if id == CommandId::ForwardCan as u8 => Ok(CommandId::ForwardCan),
+            id if id == CommandId::CustomAppData as u8 => Ok(CommandId::CustomAppData),
             id if id == CommandId::GetValuesSelective as u8 => Ok(CommandId::GetValuesSelective),
+            id if id == CommandId::CanFwdFrame as u8 => Ok(CommandId::CanFwdFrame),
+            id if id == CommandId::GetCustomConfigXml as u8 => Ok(CommandId::GetCustomConfigXml),
+            id if id == CommandId::GetCustomConfig as u8 => Ok(CommandId::GetCustomConfig),
+            id if id == CommandId::GetCustomConfigDefault as u8 => {
+                Ok(CommandId::GetCustomConfigDefault)
+            }
+            id if id == CommandId::SetCustomConfig as u8 => Ok(CommandId::SetCustomConfig),
+            id if id == CommandId::LispReadCode as u8 => Ok(CommandId::LispReadCode),
+            id if id == CommandId::LispWriteCode as u8 => Ok(CommandId::LispWriteCode),
+            id if id == CommandId::LispEraseCode as u8 => Ok(CommandId::LispEraseCode),
+            id if id == CommandId::LispSetRunning as u8 => Ok(CommandId::LispSetRunning),
+            id if id == CommandId::LispGetStats as u8 => Ok(CommandId::LispGetStats),
+            id if id == CommandId::LispPrint as u8 => Ok(CommandId::LispPrint),
+            id if id == CommandId::LispReplCmd as u8 => Ok(CommandId::LispReplCmd),
+            id if id == CommandId::LispStreamCode as u8 => Ok(CommandId::LispStreamCode),
+            id if id == CommandId::FileList as u8 => Ok(CommandId::FileList),
+            id if id == CommandId::FileRead as u8 => Ok(CommandId::FileRead),
+            id if id == CommandId::FileWrite as u8 => Ok(CommandId::FileWrite),
+            id if id == CommandId::FileMkdir as u8 => Ok(CommandId::FileMkdir),
+            id if id == CommandId::FileRemove as u8 => Ok(CommandId::FileRemove),
             id => Err(DecodeError::UnknownPacket { id }),
         }
     }
 }
 
-/// A bitmask used with [`Command::GetValuesSelective`] to request specific
-/// telemetry fields. This allows for efficient communication by requesting only
-/// the data you need, reducing bandwidth and processing overhead. Each flag
-/// corresponds to a field in the [`Values`] struct.
-///
-/// # Example
-///
-/// ```rust
-/// use vesc::ValuesMask;
-///
-/// let mask = ValuesMask::RPM | ValuesMask::WATT_HOURS | ValuesMask::CONTROLLER_ID;
-/// ```
-#[derive(Debug, Copy, Clone)]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct ValuesMask(u32);
-
-bitflags! {
-    impl ValuesMask: u32 {
-        const TEMP_MOSFET           = 1 << 0;
-        const TEMP_MOTOR            = 1 << 1;
-        const AVG_CURRENT_MOTOR     = 1 << 2;
-        const AVG_CURRENT_INPUT     = 1 << 3;
-        const AVG_CURRENT_D         = 1 << 4;
-        const AVG_CURRENT_Q         = 1 << 5;
-        const DUTY_CYCLE            = 1 << 6;
-        const RPM                   = 1 << 7;
-        const VOLTAGE_IN            = 1 << 8;
-        const AMP_HOURS             = 1 << 9;
-        const AMP_HOURS_CHARGED     = 1 << 10;
-        const WATT_HOURS            = 1 << 11;
-        const WATT_HOURS_CHARGED    = 1 << 12;
-        const TACHOMETER            = 1 << 13;
-        const TACHOMETER_ABS        = 1 << 14;
-        const FAULT_CODE            = 1 << 15;
-        const PID_POS               = 1 << 16;
-        const CONTROLLER_ID         = 1 << 17;
-        const TEMP_MOSFET_ALL       = 1 << 18;
-        const AVG_VOLTAGE_D         = 1 << 19;
-        const AVG_VOLTAGE_Q         = 1 << 20;
-        const STATUS                = 1 << 21;
-    }
-}
-
 /// Commands that can be sent to a VESC controller.
 ///
 /// Each variant represents a different operation that can be performed on the
@@ -144,10 +152,148 @@ pub enum Command<'a> {
     /// compared to [`GetValues`], making it more efficient when only selected
     /// data fields are needed.
     GetValuesSelective(ValuesMask),
+
+    /// Reads a chunk of the stored LispBM script. Takes the number of bytes to
+    /// read and the offset to start reading from.
+    LispReadCode { len: u32, offset: u32 },
+
+    /// Writes a chunk of a LispBM script into the script storage at the given
+    /// offset. Scripts larger than a frame must be written in several chunks,
+    /// and the storage must be erased with [`LispEraseCode`] beforehand.
+    ///
+    /// [`LispEraseCode`]: Self::LispEraseCode
+    LispWriteCode { offset: u32, data: &'a [u8] },
+
+    /// Erases the LispBM script storage. Takes the size of the script that is
+    /// going to be written afterwards.
+    LispEraseCode(u32),
+
+    /// Starts (`true`) or stops (`false`) the stored LispBM script.
+    LispSetRunning(bool),
+
+    /// Requests LispBM runtime statistics, such as CPU and memory usage, along
+    /// with the values of numeric global bindings.
+    LispGetStats,
+
+    /// Evaluates an expression in the LispBM REPL. The result is sent back as
+    /// [`CommandReply::LispPrint`].
+    LispReplCmd(&'a str),
+
+    /// Streams a chunk of LispBM code to be evaluated without storing it in the
+    /// script storage. Takes the chunk offset, the total length of the code,
+    /// whether to restart the runtime before evaluation, and the chunk itself.
+    LispStreamCode {
+        offset: u32,
+        total_len: u32,
+        restart: bool,
+        data: &'a [u8],
+    },
+
+    /// Lists the entries of a directory on the controller's file system. Large
+    /// directories are listed in pages; to request the next page, pass the name
+    /// of the last entry received as `from`.
+    FileList {
+        path: &'a str,
+        from: Option<&'a str>,
+    },
+
+    /// Reads a chunk of a file starting at the given offset. The chunk size is
+    /// chosen by the firmware to fit into a single reply.
+    FileRead { path: &'a str, offset: u32 },
+
+    /// Writes a chunk of a file at the given offset. Takes the path, the chunk
+    /// offset, the total size of the file, and the chunk itself.
+    FileWrite {
+        path: &'a str,
+        offset: u32,
+        total_len: u32,
+        data: &'a [u8],
+    },
+
+    /// Creates a directory, including any missing parents.
+    FileMkdir(&'a str),
+
+    /// Removes a file or an empty directory.
+    FileRemove(&'a str),
+
+    /// Reads a chunk of the compressed XML that describes a custom
+    /// configuration, such as one published by custom hardware or a package.
+    /// Takes the configuration index, the number of bytes to read and the
+    /// offset to start reading from. See [`CustomConfigXmlDownload`] for a
+    /// helper that downloads the whole XML.
+    ///
+    /// [`CustomConfigXmlDownload`]: crate::CustomConfigXmlDownload
+    GetCustomConfigXml { index: u8, len: u32, offset: u32 },
+
+    /// Requests the serialized values of the custom configuration with the
+    /// given index.
+    GetCustomConfig(u8),
+
+    /// Requests the serialized default values of the custom configuration with
+    /// the given index.
+    GetCustomConfigDefault(u8),
+
+    /// Writes the serialized values of the custom configuration with the given
+    /// index.
+    SetCustomConfig { index: u8, data: &'a [u8] },
+
+    /// Sends an opaque payload to a custom application running on the VESC.
+    /// To send a structured payload without serializing it into a separate
+    /// buffer first, see [`encode_custom`].
+    CustomAppData(&'a [u8]),
+
+    /// Transmits a raw frame on the VESC's CAN bus, which lets a host without
+    /// a CAN interface talk to other CAN devices. Takes an 11-bit standard or
+    /// a 29-bit extended identifier, depending on `extended`, and up to 8 data
+    /// bytes.
+    CanFwdFrame {
+        id: u32,
+        extended: bool,
+        data: &'a [u8],
+    },
 }
 
 impl<'a> Command<'a> {
-    fn pack_into(&self, packer: &mut Packer) -> Result<(), EncodeError> {
+    /// The length of the longest frame the firmware accepts, which is a long
+    /// frame with a payload of 512 bytes. A buffer of this length fits any
+    /// command that the firmware would not drop.
+    pub const MAX_FRAME_LEN: usize = 3 + PAYLOAD_MAX_LEN + 3;
+
+    /// Returns the length of the frame the command is encoded into, so that
+    /// buffers can be sized exactly.
+    ///
+    /// The length includes any commands nested in [`Command::ForwardCan`],
+    /// and the longer header of frames with a payload over 255 bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`encode`] would, apart from
+    /// [`EncodeError::BufferTooSmall`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vesc::Command;
+    ///
+    /// let command = Command::ForwardCan(3, &Command::SetRpm(1500));
+    /// let mut buf = [0u8; Command::MAX_FRAME_LEN];
+    /// let len = vesc::encode(command, &mut buf).unwrap();
+    /// assert_eq!(command.encoded_len(), Ok(len));
+    /// ```
+    pub fn encoded_len(&self) -> Result<usize, EncodeError> {
+        let payload_len = self.payload_len()?;
+        Ok(header_len(payload_len)? + payload_len + 3)
+    }
+
+    /// Returns the length of the payload, by packing it without storing it.
+    pub(crate) fn payload_len(&self) -> Result<usize, EncodeError> {
+        let mut discard = |_: &[u8]| ();
+        let mut packer = Packer::with_sink(&mut discard);
+        self.pack_into(&mut packer)?;
+        Ok(packer.pos)
+    }
+
+    pub(crate) fn pack_into(&self, packer: &mut Packer) -> Result<(), EncodeError> {
         match self {
             Self::GetValues => {
                 packer.pack_u8(CommandId::GetValues as u8)?;
@@ -173,45 +319,244 @@ impl<'a> Command<'a> {
                 packer.pack_u8(CommandId::GetValuesSelective as u8)?;
                 packer.pack_u32(mask.bits())?;
             }
+            Self::LispReadCode { len, offset } => {
+                packer.pack_u8(CommandId::LispReadCode as u8)?;
+                packer.pack_u32(*len)?;
+                packer.pack_u32(*offset)?;
+            }
+            Self::LispWriteCode { offset, data } => {
+                packer.pack_u8(CommandId::LispWriteCode as u8)?;
+                packer.pack_u32(*offset)?;
+                packer.pack_bytes(data)?;
+            }
+            Self::LispEraseCode(size) => {
+                packer.pack_u8(CommandId::LispEraseCode as u8)?;
+                packer.pack_u32(*size)?;
+            }
+            Self::LispSetRunning(running) => {
+                packer.pack_u8(CommandId::LispSetRunning as u8)?;
+                packer.pack_u8(*running as u8)?;
+            }
+            Self::LispGetStats => {
+                packer.pack_u8(CommandId::LispGetStats as u8)?;
+            }
+            Self::LispReplCmd(expr) => {
+                packer.pack_u8(CommandId::LispReplCmd as u8)?;
+                packer.pack_str(expr)?;
+            }
+            Self::LispStreamCode {
+                offset,
+                total_len,
+                restart,
+                data,
+            } => {
+                packer.pack_u8(CommandId::LispStreamCode as u8)?;
+                packer.pack_u32(*offset)?;
+                packer.pack_u32(*total_len)?;
+                packer.pack_u8(*restart as u8)?;
+                packer.pack_bytes(data)?;
+            }
+            Self::FileList { path, from } => {
+                packer.pack_u8(CommandId::FileList as u8)?;
+                packer.pack_str(path)?;
+                if let Some(from) = from {
+                    packer.pack_str(from)?;
+                }
+            }
+            Self::FileRead { path, offset } => {
+                packer.pack_u8(CommandId::FileRead as u8)?;
+                packer.pack_str(path)?;
+                packer.pack_u32(*offset)?;
+            }
+            Self::FileWrite {
+                path,
+                offset,
+                total_len,
+                data,
+            } => {
+                packer.pack_u8(CommandId::FileWrite as u8)?;
+                packer.pack_str(path)?;
+                packer.pack_u32(*offset)?;
+                packer.pack_u32(*total_len)?;
+                packer.pack_bytes(data)?;
+            }
+            Self::FileMkdir(path) => {
+                packer.pack_u8(CommandId::FileMkdir as u8)?;
+                packer.pack_str(path)?;
+            }
+            Self::FileRemove(path) => {
+                packer.pack_u8(CommandId::FileRemove as u8)?;
+                packer.pack_str(path)?;
+            }
+            Self::GetCustomConfigXml { index, len, offset } => {
+                packer.pack_u8(CommandId::GetCustomConfigXml as u8)?;
+                packer.pack_u8(*index)?;
+                packer.pack_u32(*len)?;
+                packer.pack_u32(*offset)?;
+            }
+            Self::GetCustomConfig(index) => {
+                packer.pack_u8(CommandId::GetCustomConfig as u8)?;
+                packer.pack_u8(*index)?;
+            }
+            Self::GetCustomConfigDefault(index) => {
+                packer.pack_u8(CommandId::GetCustomConfigDefault as u8)?;
+                packer.pack_u8(*index)?;
+            }
+            Self::SetCustomConfig { index, data } => {
+                packer.pack_u8(CommandId::SetCustomConfig as u8)?;
+                packer.pack_u8(*index)?;
+                packer.pack_bytes(data)?;
+            }
+            Self::CustomAppData(data) => {
+                packer.pack_u8(CommandId::CustomAppData as u8)?;
+                packer.pack_bytes(data)?;
+            }
+            Self::CanFwdFrame { id, extended, data } => {
+                let id_max = if *extended { 0x1fff_ffff } else { 0x7ff };
+                if *id > id_max || data.len() > 8 {
+                    return Err(EncodeError::InvalidCanFrame);
+                }
+                packer.pack_u8(CommandId::CanFwdFrame as u8)?;
+                packer.pack_u32(*id)?;
+                packer.pack_u8(*extended as u8)?;
+                packer.pack_bytes(data)?;
+            }
         }
         Ok(())
     }
 }
 
-/// Telemetry data returned by the motor controller.
+/// LispBM runtime statistics returned by [`Command::LispGetStats`].
 ///
-/// Contains temperatures, currents, voltages, rpm, and so on. Returned by
-/// [`Command::GetValues`] or [`Command::GetValuesSelective`].
+/// Usage figures are in percent. Numeric global bindings are borrowed from the
+/// reply frame and can be walked with [`bindings`].
 ///
-/// With [`Command::GetValuesSelective`], only the fields specified by the
-/// [`ValuesMask`] are populated; all others remain at their default.
-#[derive(Debug, Copy, Clone, Default)]
+/// [`bindings`]: Self::bindings
+#[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Values {
-    pub temp_mosfet: f32,
-    pub temp_motor: f32,
-    pub avg_current_motor: f32,
-    pub avg_current_input: f32,
-    pub avg_current_d: f32,
-    pub avg_current_q: f32,
-    pub duty_cycle: f32,
-    pub rpm: f32,
-    pub voltage_in: f32,
-    pub amp_hours: f32,
-    pub amp_hours_charged: f32,
-    pub watt_hours: f32,
-    pub watt_hours_charged: f32,
-    pub tachometer: i32,
-    pub tachometer_abs: i32,
-    pub fault_code: u8,
-    pub pid_pos: f32,
-    pub controller_id: u8,
-    pub temp_mosfet1: f32,
-    pub temp_mosfet2: f32,
-    pub temp_mosfet3: f32,
-    pub avg_voltage_d: f32,
-    pub avg_voltage_q: f32,
-    pub status: u8,
+pub struct LispStats<'a> {
+    pub cpu_use: f32,
+    pub heap_use: f32,
+    pub mem_use: f32,
+    bindings: &'a [u8],
+}
+
+impl<'a> LispStats<'a> {
+    /// Returns an iterator over the numeric global bindings of the running
+    /// script.
+    pub fn bindings(&self) -> LispBindings<'a> {
+        LispBindings {
+            unpacker: Unpacker::new(self.bindings),
+        }
+    }
+}
+
+/// A numeric global binding of a running LispBM script.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LispBinding<'a> {
+    pub name: &'a str,
+    pub value: f32,
+}
+
+/// An iterator over the global bindings in [`LispStats`].
+pub struct LispBindings<'a> {
+    unpacker: Unpacker<'a>,
+}
+
+impl<'a> LispBindings<'a> {
+    fn unpack_next(&mut self) -> Result<LispBinding<'a>, DecodeError> {
+        Ok(LispBinding {
+            name: self.unpacker.unpack_str()?,
+            value: self.unpacker.unpack_f32_auto()?,
+        })
+    }
+}
+
+impl<'a> Iterator for LispBindings<'a> {
+    type Item = LispBinding<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.unpacker.pos == self.unpacker.buf.len() {
+            return None;
+        }
+        // Bindings are validated when the reply is decoded, so this can only
+        // fail if the invariant is broken.
+        self.unpack_next().ok()
+    }
+}
+
+/// A page of directory entries returned by [`Command::FileList`].
+///
+/// Entries are borrowed from the reply frame and can be walked with
+/// [`entries`].
+///
+/// [`entries`]: Self::entries
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FileList<'a> {
+    /// Whether the directory has more entries than fit into this page.
+    pub has_more: bool,
+    entries: &'a [u8],
+}
+
+impl<'a> FileList<'a> {
+    /// Returns an iterator over the directory entries in this page.
+    pub fn entries(&self) -> FileEntries<'a> {
+        FileEntries {
+            unpacker: Unpacker::new(self.entries),
+        }
+    }
+
+    /// Returns the entry name to pass as `from` in [`Command::FileList`] to
+    /// request the next page, or `None` if this is the last page.
+    pub fn next_from(&self) -> Option<&'a str> {
+        if !self.has_more {
+            return None;
+        }
+        self.entries().last().map(|entry| entry.name)
+    }
+}
+
+/// An entry of a directory listing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FileEntry<'a> {
+    pub name: &'a str,
+    pub is_dir: bool,
+    /// The file size in bytes; always zero for directories.
+    pub size: u32,
+}
+
+/// An iterator over the entries in [`FileList`].
+pub struct FileEntries<'a> {
+    unpacker: Unpacker<'a>,
+}
+
+impl<'a> FileEntries<'a> {
+    fn unpack_next(&mut self) -> Result<FileEntry<'a>, DecodeError> {
+        let name = self.unpacker.unpack_str()?;
+        // The firmware reports directories with a negative size.
+        let size = self.unpacker.unpack_i32()?;
+        Ok(FileEntry {
+            name,
+            is_dir: size < 0,
+            size: size.max(0) as u32,
+        })
+    }
+}
+
+impl<'a> Iterator for FileEntries<'a> {
+    type Item = FileEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.unpacker.pos == self.unpacker.buf.len() {
+            return None;
+        }
+        // Entries are validated when the reply is decoded, so this can only
+        // fail if the invariant is broken.
+        self.unpack_next().ok()
+    }
 }
 
 /// Reply messages received from the VESC in response to commands.
@@ -220,132 +565,296 @@ pub struct Values {
 /// controller after sending commands.
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub enum CommandReply {
+pub enum CommandReply<'a> {
     /// Complete telemetry data in response to [`Command::GetValues`]. Contains
     /// all available sensor readings and status information.
+    ///
+    /// Older firmware releases send fewer fields, which are left at their
+    /// default, while fields appended by newer releases are ignored.
     GetValues(Values),
 
     /// Selective telemetry data in response to [`Command::GetValuesSelective`].
     /// Contains only the data fields that were requested via the
     /// [`ValuesMask`]. Non-requested fields will have default values.
     GetValuesSelective(Values),
+
+    /// A chunk of the stored LispBM script in response to
+    /// [`Command::LispReadCode`]. Contains the total script length, the chunk
+    /// offset and the chunk itself.
+    LispReadCode {
+        total_len: u32,
+        offset: u32,
+        data: &'a [u8],
+    },
+
+    /// Acknowledges [`Command::LispWriteCode`]. Contains whether the chunk was
+    /// written and its offset.
+    LispWriteCode { ok: bool, offset: u32 },
+
+    /// Acknowledges [`Command::LispEraseCode`]. Contains whether the script
+    /// storage was erased.
+    LispEraseCode(bool),
+
+    /// Acknowledges [`Command::LispSetRunning`]. Contains whether the request
+    /// succeeded.
+    LispSetRunning(bool),
+
+    /// Runtime statistics in response to [`Command::LispGetStats`].
+    LispGetStats(LispStats<'a>),
+
+    /// Output printed by a LispBM script or the REPL.
+    LispPrint(&'a str),
+
+    /// Acknowledges [`Command::LispStreamCode`]. Contains the chunk offset and
+    /// the firmware result code, where negative values indicate an error.
+    LispStreamCode { offset: u32, result: i16 },
+
+    /// A page of directory entries in response to [`Command::FileList`].
+    FileList(FileList<'a>),
+
+    /// A chunk of a file in response to [`Command::FileRead`]. Contains the
+    /// chunk offset, the total size of the file, and the chunk itself. A
+    /// negative size means the file could not be read.
+    FileRead {
+        offset: u32,
+        total_len: i32,
+        data: &'a [u8],
+    },
+
+    /// Acknowledges [`Command::FileWrite`]. Contains whether the chunk was
+    /// written and its offset.
+    FileWrite { ok: bool, offset: u32 },
+
+    /// Acknowledges [`Command::FileMkdir`]. Contains whether the directory
+    /// was created.
+    FileMkdir(bool),
+
+    /// Acknowledges [`Command::FileRemove`]. Contains whether the entry was
+    /// removed.
+    FileRemove(bool),
+
+    /// A chunk of the compressed custom configuration XML in response to
+    /// [`Command::GetCustomConfigXml`]. Contains the configuration index, the
+    /// total size of the compressed XML, the chunk offset and the chunk itself.
+    GetCustomConfigXml {
+        index: u8,
+        total_len: u32,
+        offset: u32,
+        data: &'a [u8],
+    },
+
+    /// Serialized custom configuration values in response to
+    /// [`Command::GetCustomConfig`].
+    GetCustomConfig { index: u8, data: &'a [u8] },
+
+    /// Serialized default custom configuration values in response to
+    /// [`Command::GetCustomConfigDefault`].
+    GetCustomConfigDefault { index: u8, data: &'a [u8] },
+
+    /// Acknowledges [`Command::SetCustomConfig`].
+    SetCustomConfig,
+
+    /// An opaque payload sent by a custom application running on the VESC.
+    /// See [`DecodeCustom`] to decode it into a structured type.
+    CustomAppData(&'a [u8]),
+
+    /// A raw frame received on the VESC's CAN bus. Only sent when the firmware
+    /// is configured to forward CAN frames to the host, e.g. with the CAN mode
+    /// set to `COMM_BRIDGE`.
+    CanFwdFrame {
+        id: u32,
+        extended: bool,
+        data: &'a [u8],
+    },
+
+    /// A reply with a packet ID this crate does not support, with a valid
+    /// checksum. Contains the packet ID and the payload following it. Only
+    /// returned by [`decode_raw`] and [`Replies::with_raw`].
+    ///
+    /// [`Replies::with_raw`]: crate::Replies::with_raw
+    Raw { id: u8, payload: &'a [u8] },
 }
 
-impl CommandReply {
-    fn unpack_from(unpacker: &mut Unpacker) -> Result<Self, DecodeError> {
+impl<'a> CommandReply<'a> {
+    pub(crate) fn unpack_from(unpacker: &mut Unpacker<'a>) -> Result<Self, DecodeError> {
         Ok(match unpacker.unpack_u8()?.try_into()? {
             CommandId::GetValues => Self::unpack_get_values(unpacker)?,
             CommandId::GetValuesSelective => Self::unpack_get_values_selective(unpacker)?,
+            CommandId::LispReadCode => Self::LispReadCode {
+                total_len: unpacker.unpack_u32()?,
+                offset: unpacker.unpack_u32()?,
+                data: unpacker.unpack_remaining(),
+            },
+            CommandId::LispWriteCode => Self::LispWriteCode {
+                ok: unpacker.unpack_u8()? != 0,
+                offset: unpacker.unpack_u32()?,
+            },
+            CommandId::LispEraseCode => Self::LispEraseCode(unpacker.unpack_u8()? != 0),
+            CommandId::LispSetRunning => Self::LispSetRunning(unpacker.unpack_u8()? != 0),
+            CommandId::LispGetStats => Self::unpack_lisp_get_stats(unpacker)?,
+            CommandId::LispPrint => Self::LispPrint(
+                core::str::from_utf8(unpacker.unpack_remaining())
+                    .map_err(|_| DecodeError::InvalidFrame)?,
+            ),
+            CommandId::LispStreamCode => Self::LispStreamCode {
+                offset: unpacker.unpack_u32()?,
+                result: unpacker.unpack_i16()?,
+            },
+            CommandId::FileList => Self::unpack_file_list(unpacker)?,
+            CommandId::FileRead => Self::FileRead {
+                offset: unpacker.unpack_u32()?,
+                total_len: unpacker.unpack_i32()?,
+                data: unpacker.unpack_remaining(),
+            },
+            CommandId::FileWrite => Self::FileWrite {
+                ok: unpacker.unpack_u8()? != 0,
+                offset: unpacker.unpack_u32()?,
+            },
+            CommandId::FileMkdir => Self::FileMkdir(unpacker.unpack_u8()? != 0),
+            CommandId::FileRemove => Self::FileRemove(unpacker.unpack_u8()? != 0),
+            CommandId::GetCustomConfigXml => Self::GetCustomConfigXml {
+                index: unpacker.unpack_u8()?,
+                total_len: unpacker.unpack_u32()?,
+                offset: unpacker.unpack_u32()?,
+                data: unpacker.unpack_remaining(),
+            },
+            CommandId::GetCustomConfig => Self::GetCustomConfig {
+                index: unpacker.unpack_u8()?,
+                data: unpacker.unpack_remaining(),
+            },
+            CommandId::GetCustomConfigDefault => Self::GetCustomConfigDefault {
+                index: unpacker.unpack_u8()?,
+                data: unpacker.unpack_remaining(),
+            },
+            CommandId::SetCustomConfig => Self::SetCustomConfig,
+            CommandId::CustomAppData => Self::CustomAppData(unpacker.unpack_remaining()),
+            CommandId::CanFwdFrame => Self::CanFwdFrame {
+                id: unpacker.unpack_u32()?,
+                extended: unpacker.unpack_u8()? != 0,
+                data: match unpacker.unpack_remaining() {
+                    data if data.len() <= 8 => data,
+                    _ => return Err(DecodeError::InvalidFrame),
+                },
+            },
             id => return Err(DecodeError::UnknownPacket { id: id as u8 }),
         })
     }
 
-    fn unpack_get_values(unpacker: &mut Unpacker) -> Result<Self, DecodeError> {
-        let values = Values {
-            temp_mosfet: unpacker.unpack_f16(10.0)?,
-            temp_motor: unpacker.unpack_f16(10.0)?,
-            avg_current_motor: unpacker.unpack_f32(100.0)?,
-            avg_current_input: unpacker.unpack_f32(100.0)?,
-            avg_current_d: unpacker.unpack_f32(100.0)?,
-            avg_current_q: unpacker.unpack_f32(100.0)?,
-            duty_cycle: unpacker.unpack_f16(1000.0)?,
-            rpm: unpacker.unpack_f32(1.0)?,
-            voltage_in: unpacker.unpack_f16(10.0)?,
-            amp_hours: unpacker.unpack_f32(10000.0)?,
-            amp_hours_charged: unpacker.unpack_f32(10000.0)?,
-            watt_hours: unpacker.unpack_f32(10000.0)?,
-            watt_hours_charged: unpacker.unpack_f32(10000.0)?,
-            tachometer: unpacker.unpack_i32()?,
-            tachometer_abs: unpacker.unpack_i32()?,
-            fault_code: unpacker.unpack_u8()?,
-            pid_pos: unpacker.unpack_f32(1000000.0)?,
-            controller_id: unpacker.unpack_u8()?,
-            temp_mosfet1: unpacker.unpack_f16(10.0)?,
-            temp_mosfet2: unpacker.unpack_f16(10.0)?,
-            temp_mosfet3: unpacker.unpack_f16(10.0)?,
-            avg_voltage_d: unpacker.unpack_f32(1000.0)?,
-            avg_voltage_q: unpacker.unpack_f32(1000.0)?,
-            status: unpacker.unpack_u8()?,
+    fn unpack_lisp_get_stats(unpacker: &mut Unpacker<'a>) -> Result<Self, DecodeError> {
+        let cpu_use = unpacker.unpack_f16(100.0)?;
+        let heap_use = unpacker.unpack_f16(100.0)?;
+        let mem_use = unpacker.unpack_f16(100.0)?;
+        let bindings = unpacker.unpack_remaining();
+
+        let mut iter = LispBindings {
+            unpacker: Unpacker::new(bindings),
         };
+        while iter.unpacker.pos < bindings.len() {
+            iter.unpack_next()?;
+        }
+
+        Ok(CommandReply::LispGetStats(LispStats {
+            cpu_use,
+            heap_use,
+            mem_use,
+            bindings,
+        }))
+    }
+
+    fn unpack_file_list(unpacker: &mut Unpacker<'a>) -> Result<Self, DecodeError> {
+        let has_more = unpacker.unpack_u8()? != 0;
+        let entries = unpacker.unpack_remaining();
+
+        let mut iter = FileEntries {
+            unpacker: Unpacker::new(entries),
+        };
+        while iter.unpacker.pos < entries.len() {
+            iter.unpack_next()?;
+        }
+
+        Ok(CommandReply::FileList(FileList { has_more, entries }))
+    }
+
+    fn unpack_get_values(unpacker: &mut Unpacker) -> Result<Self, DecodeError> {
+        let values = Values::unpack_available(unpacker)?;
         Ok(CommandReply::GetValues(values))
     }
 
     fn unpack_get_values_selective(unpacker: &mut Unpacker) -> Result<Self, DecodeError> {
-        let mut values = Values::default();
         let mask = ValuesMask::from_bits_retain(unpacker.unpack_u32()?);
-
-        if mask.contains(ValuesMask::TEMP_MOSFET) {
-            values.temp_mosfet = unpacker.unpack_f16(10.0)?;
-        }
-        if mask.contains(ValuesMask::TEMP_MOTOR) {
-            values.temp_motor = unpacker.unpack_f16(10.0)?;
-        }
-        if mask.contains(ValuesMask::AVG_CURRENT_MOTOR) {
-            values.avg_current_motor = unpacker.unpack_f32(100.0)?;
-        }
-        if mask.contains(ValuesMask::AVG_CURRENT_INPUT) {
-            values.avg_current_input = unpacker.unpack_f32(100.0)?;
-        }
-        if mask.contains(ValuesMask::AVG_CURRENT_D) {
-            values.avg_current_d = unpacker.unpack_f32(100.0)?;
-        }
-        if mask.contains(ValuesMask::AVG_CURRENT_Q) {
-            values.avg_current_q = unpacker.unpack_f32(100.0)?;
-        }
-        if mask.contains(ValuesMask::DUTY_CYCLE) {
-            values.duty_cycle = unpacker.unpack_f16(1000.0)?;
-        }
-        if mask.contains(ValuesMask::RPM) {
-            values.rpm = unpacker.unpack_f32(1.0)?;
-        }
-        if mask.contains(ValuesMask::VOLTAGE_IN) {
-            values.voltage_in = unpacker.unpack_f16(10.0)?;
-        }
-        if mask.contains(ValuesMask::AMP_HOURS) {
-            values.amp_hours = unpacker.unpack_f32(10000.0)?;
-        }
-        if mask.contains(ValuesMask::AMP_HOURS_CHARGED) {
-            values.amp_hours_charged = unpacker.unpack_f32(10000.0)?;
-        }
-        if mask.contains(ValuesMask::WATT_HOURS) {
-            values.watt_hours = unpacker.unpack_f32(10000.0)?;
-        }
-        if mask.contains(ValuesMask::WATT_HOURS_CHARGED) {
-            values.watt_hours_charged = unpacker.unpack_f32(10000.0)?;
-        }
-        if mask.contains(ValuesMask::TACHOMETER) {
-            values.tachometer = unpacker.unpack_i32()?;
-        }
-        if mask.contains(ValuesMask::TACHOMETER_ABS) {
-            values.tachometer_abs = unpacker.unpack_i32()?;
-        }
-        if mask.contains(ValuesMask::FAULT_CODE) {
-            values.fault_code = unpacker.unpack_u8()?;
-        }
-        if mask.contains(ValuesMask::PID_POS) {
-            values.pid_pos = unpacker.unpack_f32(1000000.0)?;
-        }
-        if mask.contains(ValuesMask::CONTROLLER_ID) {
-            values.controller_id = unpacker.unpack_u8()?;
-        }
-        if mask.contains(ValuesMask::TEMP_MOSFET_ALL) {
-            values.temp_mosfet1 = unpacker.unpack_f16(10.0)?;
-            values.temp_mosfet2 = unpacker.unpack_f16(10.0)?;
-            values.temp_mosfet3 = unpacker.unpack_f16(10.0)?;
-        }
-        if mask.contains(ValuesMask::AVG_VOLTAGE_D) {
-            values.avg_voltage_d = unpacker.unpack_f32(1000.0)?;
-        }
-        if mask.contains(ValuesMask::AVG_VOLTAGE_Q) {
-            values.avg_voltage_q = unpacker.unpack_f32(1000.0)?;
-        }
-        if mask.contains(ValuesMask::STATUS) {
-            values.status = unpacker.unpack_u8()?;
-        }
+        let values = Values::unpack_from(unpacker, mask)?;
         Ok(CommandReply::GetValuesSelective(values))
     }
 }
 
+/// A structured payload sent to a custom application running on the VESC via
+/// `COMM_CUSTOM_APP_DATA`, encoded with [`encode_custom`].
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::{EncodeCustom, EncodeError};
+///
+/// struct Lights(bool);
+///
+/// impl EncodeCustom for Lights {
+///     fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+///         *buf.first_mut().ok_or(EncodeError::BufferTooSmall)? = self.0 as u8;
+///         Ok(1)
+///     }
+/// }
+///
+/// let mut buf = [0u8; 16];
+/// let len = vesc::encode_custom(&Lights(true), &mut buf).unwrap();
+/// assert_eq!(buf[..len], [2, 2, 36, 1, 218, 3, 3]);
+/// ```
+pub trait EncodeCustom {
+    /// Writes the payload into `buf`. Returns the number of bytes written.
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError>;
+}
+
+/// A structured payload received from a custom application running on the
+/// VESC, decoded from [`CommandReply::CustomAppData`], for example through
+/// [`Replies::with_custom`].
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::{DecodeCustom, DecodeError};
+///
+/// struct Lights(bool);
+///
+/// impl DecodeCustom<'_> for Lights {
+///     fn decode(payload: &[u8]) -> Result<Self, DecodeError> {
+///         match payload {
+///             [on] => Ok(Lights(*on != 0)),
+///             _ => Err(DecodeError::InvalidFrame),
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`Replies::with_custom`]: crate::Replies::with_custom
+pub trait DecodeCustom<'a>: Sized {
+    /// Reads the payload from the data of a [`CommandReply::CustomAppData`].
+    fn decode(payload: &'a [u8]) -> Result<Self, DecodeError>;
+}
+
+/// Encodes an [`EncodeCustom`] payload into a byte buffer as custom app data.
+///
+/// Writes the encoded frame to `buf`. Returns the number of bytes written on
+/// success, or an error if encoding fails.
+///
+/// There is no streaming counterpart like [`encode_to`], as the payload is
+/// written into the buffer by [`EncodeCustom::encode`], which a sink cannot
+/// provide.
+pub fn encode_custom<T: EncodeCustom>(payload: &T, buf: &mut [u8]) -> Result<usize, EncodeError> {
+    write_with(buf, |packer| {
+        packer.pack_u8(CommandId::CustomAppData as u8)?;
+        packer.pack_with(|buf| payload.encode(buf))
+    })
+}
+
 /// Encodes a [`Command`] into a byte buffer.
 ///
 /// Writes the encoded frame to `buf`. Returns the number of bytes written on
@@ -363,15 +872,65 @@ impl CommandReply {
 ///  }
 /// ```
 pub fn encode(command: Command, buf: &mut [u8]) -> Result<usize, EncodeError> {
-    let mut packer = Packer::new(buf);
-    packer.pack_u8(FRAME_START_SHORT)?;
-    packer.pack_u8(0)?;
-    command.pack_into(&mut packer)?;
-    let payload_len = packer.pos - 2;
-    packer.buf[1] = payload_len as u8;
-    packer.pack_u16(CRC16.checksum(&packer.buf[2..2 + payload_len]))?;
-    packer.pack_u8(FRAME_END)?;
-    Ok(packer.pos)
+    write_with(buf, |packer| command.pack_into(packer))
+}
+
+/// Encodes a [`Command`] into a sink, without an intermediate buffer.
+///
+/// The frame is passed to `sink` in pieces as it is encoded, and its checksum
+/// is computed along the way, so large payloads such as configuration or file
+/// chunks need no buffer of their own. Returns the number of bytes passed to
+/// the sink.
+///
+/// The sink cannot fail, but a writer that can, such as an
+/// `embedded_io::Write` implementation, can keep its first error and skip the
+/// remaining pieces, as in the example below.
+///
+/// # Errors
+///
+/// Returns [`EncodeError::PayloadTooLarge`] if the payload is longer than the
+/// 512 bytes the firmware accepts, in which case nothing is passed to the
+/// sink.
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::Command;
+///
+/// let mut frame = Vec::new();
+/// let mut result = Ok(());
+/// vesc::encode_to(Command::SetRpm(1500), |bytes| {
+///     if result.is_ok() {
+///         result = std::io::Write::write_all(&mut frame, bytes);
+///     }
+/// })
+/// .unwrap();
+/// result.unwrap();
+///
+/// assert_eq!(frame, [2, 5, 8, 0, 0, 5, 220, 247, 41, 3]);
+/// ```
+pub fn encode_to(command: Command, mut sink: impl FnMut(&[u8])) -> Result<usize, EncodeError> {
+    // The header carries the payload length, so the payload is packed once
+    // to measure it before it is passed to the sink.
+    let payload_len = command.payload_len()?;
+    let header_len = header_len(payload_len)?;
+    if header_len == 3 {
+        sink(&[FRAME_START_LONG]);
+        sink(&(payload_len as u16).to_be_bytes());
+    } else {
+        sink(&[FRAME_START_SHORT, payload_len as u8]);
+    }
+
+    let mut digest = CRC16.digest();
+    let mut payload_sink = |bytes: &[u8]| {
+        digest.update(bytes);
+        sink(bytes);
+    };
+    command.pack_into(&mut Packer::with_sink(&mut payload_sink))?;
+
+    let [crc_hi, crc_lo] = digest.finalize().to_be_bytes();
+    sink(&[crc_hi, crc_lo, FRAME_END]);
+    Ok(header_len + payload_len + 3)
 }
 
 /// Decodes a [`CommandReply`] from a byte buffer.
@@ -379,6 +938,9 @@ pub fn encode(command: Command, buf: &mut [u8]) -> Result<usize, EncodeError> {
 /// Returns the consumed number of bytes and decoded reply on success, or an
 /// error if the frame is invalid.
 ///
+/// Frames are checked and their payload interpreted in one go. To handle
+/// frames without interpreting their payload, see [`Frame`].
+///
 /// # Example
 ///
 /// ```no_run
@@ -392,24 +954,39 @@ pub fn encode(command: Command, buf: &mut [u8]) -> Result<usize, EncodeError> {
 ///     _ => (),
 /// }
 /// ```
-pub fn decode(buf: &[u8]) -> Result<(usize, CommandReply), DecodeError> {
+pub fn decode(buf: &[u8]) -> Result<(usize, CommandReply<'_>), DecodeError> {
     let mut unpacker = Unpacker::new(buf);
 
-    let frame_start = unpacker.unpack_u8()?;
-    if frame_start != FRAME_START_SHORT {
+    let payload_len = match unpacker.unpack_u8()? {
+        FRAME_START_SHORT => unpacker.unpack_u8()? as usize,
+        FRAME_START_LONG => unpacker.unpack_u16()? as usize,
+        _ => return Err(DecodeError::InvalidFrame),
+    };
+    if payload_len > PAYLOAD_MAX_LEN {
         return Err(DecodeError::InvalidFrame);
     }
-    let payload_len = unpacker.unpack_u8()? as usize;
-    let reply = CommandReply::unpack_from(&mut unpacker)?;
-
-    // Knowing the payload length upfront isn't strictly necessary here, but it
-    // provides an extra validation step: we can confirm that the frame is
-    // well-formed and report an error if the declared length doesn't match the
-    // actual payload length.
-    if payload_len != unpacker.pos - (frame_start as usize) {
-        return Err(DecodeError::InvalidFrame);
-    }
-    let payload = &unpacker.buf[(frame_start as usize)..unpacker.pos];
+    let payload_start = unpacker.pos;
+    let payload_end = buf.len().min(payload_start + payload_len);
+    let payload = &buf[payload_start..payload_end];
+    let truncated = payload.len() < payload_len;
+
+    // Replies are parsed within the bounds of the declared payload, and as
+    // much of it as is available. Parsing a partial payload lets false frame
+    // starts be rejected early instead of waiting for data that may never
+    // come. Running out of data within a complete payload, or not consuming
+    // it all, means the frame is not well-formed.
+    let mut payload_unpacker = Unpacker::new(payload);
+    let reply = match CommandReply::unpack_from(&mut payload_unpacker) {
+        Ok(_) | Err(DecodeError::IncompleteData) if truncated => {
+            return Err(DecodeError::IncompleteData);
+        }
+        Ok(_) if payload_unpacker.pos != payload_len => return Err(DecodeError::InvalidFrame),
+        Ok(reply) => reply,
+        Err(DecodeError::IncompleteData) => return Err(DecodeError::InvalidFrame),
+        Err(err) => return Err(err),
+    };
+    unpacker.pos = payload_end;
+
     let checksum_expected = unpacker.unpack_u16()?;
     if unpacker.unpack_u8()? != FRAME_END {
         return Err(DecodeError::InvalidFrame);
@@ -423,3 +1000,29 @@ pub fn decode(buf: &[u8]) -> Result<(usize, CommandReply), DecodeError> {
     }
     Ok((unpacker.pos, reply))
 }
+
+/// Decodes a [`CommandReply`] from a byte buffer like [`decode`], but returns
+/// replies with an unsupported packet ID as [`CommandReply::Raw`] instead of
+/// failing with [`DecodeError::UnknownPacket`].
+///
+/// This lets newer firmware packets be handled before the crate supports
+/// them. Their checksum is verified, but their payload is left as is.
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::CommandReply;
+///
+/// let (_, reply) = vesc::decode_raw(&[2, 2, 200, 1, 143, 220, 3]).unwrap();
+/// assert!(matches!(reply, CommandReply::Raw { id: 200, payload: [1] }));
+/// ```
+pub fn decode_raw(buf: &[u8]) -> Result<(usize, CommandReply<'_>), DecodeError> {
+    match decode(buf) {
+        Err(DecodeError::UnknownPacket { id }) => {
+            let frame = Frame::parse(buf)?;
+            let payload = &frame.payload()[1..];
+            Ok((frame.as_bytes().len(), CommandReply::Raw { id, payload }))
+        }
+        result => result,
+    }
+}
@@ -0,0 +1,410 @@
+#[cfg(feature = "std")]
+use std::{string::String, vec::Vec};
+
+use crate::packer::{Packer, Unpacker};
+use crate::{Command, CommandReply, DecodeError, EncodeError};
+
+/// Errors that can occur while downloading or reading a custom configuration.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum CustomConfigError {
+    #[error("the buffer provided for the configuration XML is too small")]
+    BufferTooSmall,
+
+    #[error("the controller replied with an empty chunk before the XML was complete")]
+    UnexpectedEnd,
+
+    #[cfg(feature = "std")]
+    #[error("the configuration XML could not be decompressed")]
+    Decompress,
+
+    #[cfg(feature = "std")]
+    #[error("the configuration XML is malformed: {0}")]
+    InvalidXml(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] String),
+
+    #[cfg(feature = "std")]
+    #[error("the parameter `{0}` has a type that cannot be serialized at a fixed offset")]
+    UnsupportedParam(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] String),
+}
+
+/// A helper for downloading the compressed XML of a custom configuration in
+/// chunks via [`Command::GetCustomConfigXml`].
+///
+/// The XML is assembled into a caller-provided buffer, so no allocation is
+/// required. Send the command returned by [`next_command`], pass every reply
+/// to [`handle_reply`], and repeat until [`data`] returns the XML.
+///
+/// # Example
+///
+/// ```no_run
+/// use vesc::CustomConfigXmlDownload;
+///
+/// let mut xml = [0u8; 4096];
+/// let mut download = CustomConfigXmlDownload::new(0, &mut xml);
+///
+/// while let Some(command) = download.next_command() {
+///     // Send `command` and pass the replies to `download.handle_reply()`.
+/// #   break;
+/// }
+/// ```
+///
+/// [`next_command`]: Self::next_command
+/// [`handle_reply`]: Self::handle_reply
+/// [`data`]: Self::data
+#[derive(Debug)]
+pub struct CustomConfigXmlDownload<'b> {
+    index: u8,
+    chunk_len: u32,
+    buf: &'b mut [u8],
+    received: usize,
+    total_len: Option<usize>,
+}
+
+impl<'b> CustomConfigXmlDownload<'b> {
+    /// The default number of bytes requested per chunk. It keeps replies well
+    /// within the firmware's payload limit.
+    pub const CHUNK_LEN: u32 = 400;
+
+    /// Creates a download of the custom configuration with the given index
+    /// into `buf`.
+    pub fn new(index: u8, buf: &'b mut [u8]) -> Self {
+        Self {
+            index,
+            chunk_len: Self::CHUNK_LEN,
+            buf,
+            received: 0,
+            total_len: None,
+        }
+    }
+
+    /// Sets the number of bytes requested per chunk.
+    pub fn with_chunk_len(mut self, chunk_len: u32) -> Self {
+        self.chunk_len = chunk_len;
+        self
+    }
+
+    /// Returns the command that requests the next chunk, or `None` once the
+    /// whole XML has been received.
+    pub fn next_command(&self) -> Option<Command<'static>> {
+        if self.is_complete() {
+            return None;
+        }
+        let remaining = self.total_len.map_or(self.chunk_len as usize, |total_len| {
+            total_len - self.received
+        });
+        Some(Command::GetCustomConfigXml {
+            index: self.index,
+            len: self.chunk_len.min(remaining as u32),
+            offset: self.received as u32,
+        })
+    }
+
+    /// Stores the chunk carried by `reply`.
+    ///
+    /// Returns `Ok(true)` if the reply was a chunk this download was waiting
+    /// for, and `Ok(false)` if it was unrelated or a duplicate and has been
+    /// ignored.
+    pub fn handle_reply(&mut self, reply: &CommandReply) -> Result<bool, CustomConfigError> {
+        let CommandReply::GetCustomConfigXml {
+            index,
+            total_len,
+            offset,
+            data,
+        } = *reply
+        else {
+            return Ok(false);
+        };
+        if index != self.index || offset as usize != self.received || self.is_complete() {
+            return Ok(false);
+        }
+
+        let total_len = total_len as usize;
+        if total_len > self.buf.len() {
+            return Err(CustomConfigError::BufferTooSmall);
+        }
+        if data.is_empty() && self.received < total_len {
+            return Err(CustomConfigError::UnexpectedEnd);
+        }
+        let len = data.len().min(total_len - self.received);
+        self.buf[self.received..self.received + len].copy_from_slice(&data[..len]);
+        self.received += len;
+        self.total_len = Some(total_len);
+        Ok(true)
+    }
+
+    /// Returns whether the whole XML has been received.
+    pub fn is_complete(&self) -> bool {
+        self.total_len == Some(self.received)
+    }
+
+    /// Returns the compressed XML once the download is complete.
+    pub fn data(&self) -> Option<&[u8]> {
+        self.is_complete().then(|| &self.buf[..self.received])
+    }
+}
+
+/// How a custom configuration parameter is serialized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigParamKind {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    /// A float serialized as a scaled 16-bit integer.
+    F16,
+    /// A float serialized as a scaled 32-bit integer.
+    F32,
+    /// A float serialized in the firmware's `float32_auto` format.
+    F32Auto,
+}
+
+impl ConfigParamKind {
+    /// Returns the number of bytes the parameter occupies in the serialized
+    /// configuration.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Bool | Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 | Self::F16 => 2,
+            Self::U32 | Self::I32 | Self::F32 | Self::F32Auto => 4,
+        }
+    }
+
+    /// Reads a parameter of this kind at `offset` of the serialized
+    /// configuration. Floats are divided by `scale`.
+    pub fn read(self, data: &[u8], offset: usize, scale: f32) -> Result<ConfigValue, DecodeError> {
+        let mut unpacker = Unpacker::new(data.get(offset..).ok_or(DecodeError::IncompleteData)?);
+        Ok(match self {
+            Self::Bool => ConfigValue::Bool(unpacker.unpack_u8()? != 0),
+            Self::U8 => ConfigValue::Int(unpacker.unpack_u8()? as i64),
+            Self::I8 => ConfigValue::Int(unpacker.unpack_i8()? as i64),
+            Self::U16 => ConfigValue::Int(unpacker.unpack_u16()? as i64),
+            Self::I16 => ConfigValue::Int(unpacker.unpack_i16()? as i64),
+            Self::U32 => ConfigValue::Int(unpacker.unpack_u32()? as i64),
+            Self::I32 => ConfigValue::Int(unpacker.unpack_i32()? as i64),
+            Self::F16 => ConfigValue::Float(unpacker.unpack_f16(scale)?),
+            Self::F32 => ConfigValue::Float(unpacker.unpack_f32(scale)?),
+            Self::F32Auto => ConfigValue::Float(unpacker.unpack_f32_auto()?),
+        })
+    }
+
+    /// Writes `value` as a parameter of this kind at `offset` of the
+    /// serialized configuration. Floats are multiplied by `scale`, and values
+    /// are converted to the parameter's type as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EncodeError::ValueOutOfRange`] if the value does not fit the
+    /// parameter's type, and [`EncodeError::BufferTooSmall`] if the parameter
+    /// does not fit into `data`.
+    pub fn write(
+        self,
+        data: &mut [u8],
+        offset: usize,
+        scale: f32,
+        value: ConfigValue,
+    ) -> Result<(), EncodeError> {
+        let mut packer = Packer::new(data.get_mut(offset..).ok_or(EncodeError::BufferTooSmall)?);
+        match self {
+            Self::Bool => packer.pack_u8((value.as_i64()? != 0) as u8),
+            Self::U8 => packer.pack_u8(value.as_int()?),
+            Self::I8 => packer.pack_i8(value.as_int()?),
+            Self::U16 => packer.pack_u16(value.as_int()?),
+            Self::I16 => packer.pack_i16(value.as_int()?),
+            Self::U32 => packer.pack_u32(value.as_int()?),
+            Self::I32 => packer.pack_i32(value.as_int()?),
+            Self::F16 => packer.pack_f16(value.as_f32(), scale),
+            Self::F32 => packer.pack_f32(value.as_f32(), scale),
+            Self::F32Auto => packer.pack_f32_auto(value.as_f32()),
+        }
+    }
+}
+
+/// A custom configuration parameter value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigValue {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+}
+
+impl ConfigValue {
+    fn as_i64(self) -> Result<i64, EncodeError> {
+        match self {
+            Self::Bool(value) => Ok(value as i64),
+            Self::Int(value) => Ok(value),
+            // The range also rejects NaN.
+            Self::Float(value) if (i64::MIN as f32..i64::MAX as f32).contains(&value) => {
+                Ok(value as i64)
+            }
+            Self::Float(_) => Err(EncodeError::ValueOutOfRange),
+        }
+    }
+
+    fn as_int<T: TryFrom<i64>>(self) -> Result<T, EncodeError> {
+        T::try_from(self.as_i64()?).map_err(|_| EncodeError::ValueOutOfRange)
+    }
+
+    fn as_f32(self) -> f32 {
+        match self {
+            Self::Bool(value) => value as u8 as f32,
+            Self::Int(value) => value as f32,
+            Self::Float(value) => value,
+        }
+    }
+}
+
+/// A parameter definition read from a custom configuration XML.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigParam {
+    pub name: String,
+    pub kind: ConfigParamKind,
+    /// The scale applied to floats serialized as integers.
+    pub scale: f32,
+    /// The byte offset of the parameter in the serialized configuration.
+    pub offset: usize,
+}
+
+#[cfg(feature = "std")]
+impl ConfigParam {
+    /// Reads the parameter value from the serialized configuration.
+    pub fn read(&self, data: &[u8]) -> Result<ConfigValue, DecodeError> {
+        self.kind.read(data, self.offset, self.scale)
+    }
+
+    /// Writes the parameter value into the serialized configuration.
+    pub fn write(&self, data: &mut [u8], value: ConfigValue) -> Result<(), EncodeError> {
+        self.kind.write(data, self.offset, self.scale, value)
+    }
+}
+
+/// Parameter definitions of a custom configuration, read from the XML
+/// returned by [`Command::GetCustomConfigXml`].
+///
+/// The definitions describe the layout of the data returned by
+/// [`Command::GetCustomConfig`] and accepted by [`Command::SetCustomConfig`],
+/// so values can be decoded and edited without knowing the configuration
+/// upfront.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomConfigParams {
+    params: Vec<ConfigParam>,
+}
+
+#[cfg(feature = "std")]
+impl CustomConfigParams {
+    /// The size of the signature that precedes the values in the serialized
+    /// configuration.
+    pub const SIGNATURE_LEN: usize = 4;
+
+    /// Reads the parameter definitions from the XML as stored on the
+    /// controller, that is, zlib compressed and prefixed with the big-endian
+    /// length of the uncompressed data.
+    pub fn from_compressed_xml(data: &[u8]) -> Result<Self, CustomConfigError> {
+        let (len, compressed) = data
+            .split_first_chunk::<4>()
+            .ok_or(CustomConfigError::Decompress)?;
+        let xml = miniz_oxide::inflate::decompress_to_vec_zlib(compressed)
+            .map_err(|_| CustomConfigError::Decompress)?;
+        if xml.len() != u32::from_be_bytes(*len) as usize {
+            return Err(CustomConfigError::Decompress);
+        }
+        let xml = String::from_utf8(xml).map_err(|_| CustomConfigError::Decompress)?;
+        Self::from_xml(&xml)
+    }
+
+    /// Reads the parameter definitions from an uncompressed XML.
+    pub fn from_xml(xml: &str) -> Result<Self, CustomConfigError> {
+        let invalid = |reason: &str| CustomConfigError::InvalidXml(reason.into());
+        let document = roxmltree::Document::parse(xml)
+            .map_err(|err| CustomConfigError::InvalidXml(err.to_string()))?;
+        let root = document.root_element();
+        fn child<'a, 'input>(
+            node: roxmltree::Node<'a, 'input>,
+            name: &str,
+        ) -> Option<roxmltree::Node<'a, 'input>> {
+            node.children().find(|child| child.has_tag_name(name))
+        }
+        fn text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> &'a str {
+            child(node, name)
+                .and_then(|child| child.text())
+                .unwrap_or("")
+                .trim()
+        }
+
+        let definitions = child(root, "Params").ok_or_else(|| invalid("missing <Params>"))?;
+        let order = child(root, "SerOrder").ok_or_else(|| invalid("missing <SerOrder>"))?;
+
+        let mut params = Vec::new();
+        let mut offset = Self::SIGNATURE_LEN;
+        for name in order.children().filter(|node| node.has_tag_name("ser")) {
+            let name = name.text().unwrap_or("").trim();
+            let definition = child(definitions, name).ok_or_else(|| {
+                CustomConfigError::InvalidXml(format!("missing parameter `{name}`"))
+            })?;
+            let kind = Self::param_kind(text(definition, "type"), text(definition, "vTx"))
+                .ok_or_else(|| CustomConfigError::UnsupportedParam(name.into()))?;
+            let scale = text(definition, "vTxDoubleScale").parse().unwrap_or(1.0);
+
+            params.push(ConfigParam {
+                name: name.into(),
+                kind,
+                scale,
+                offset,
+            });
+            offset += kind.size();
+        }
+        Ok(Self { params })
+    }
+
+    // Maps the XML parameter type (`CFG_T_*`) and transmission type
+    // (`VESC_TX_*`) to the serialized representation.
+    fn param_kind(param_type: &str, tx_type: &str) -> Option<ConfigParamKind> {
+        const CFG_T_QSTRING: &str = "3";
+        const CFG_T_ENUM: &str = "4";
+        const CFG_T_BOOL: &str = "5";
+        const CFG_T_BITFIELD: &str = "6";
+
+        Some(match (param_type, tx_type) {
+            (CFG_T_QSTRING, _) => return None,
+            (CFG_T_BOOL, _) => ConfigParamKind::Bool,
+            (_, "1") => ConfigParamKind::U8,
+            (_, "2") => ConfigParamKind::I8,
+            (_, "3") => ConfigParamKind::U16,
+            (_, "4") => ConfigParamKind::I16,
+            (_, "5") => ConfigParamKind::U32,
+            (_, "6") => ConfigParamKind::I32,
+            (_, "7") => ConfigParamKind::F16,
+            (_, "8") => ConfigParamKind::F32,
+            (_, "9") => ConfigParamKind::F32Auto,
+            // Enums and bitfields without an explicit transmission type are
+            // sent as a single byte.
+            (CFG_T_ENUM | CFG_T_BITFIELD, _) => ConfigParamKind::U8,
+            _ => return None,
+        })
+    }
+
+    /// Returns the parameter definitions in serialization order.
+    pub fn params(&self) -> &[ConfigParam] {
+        &self.params
+    }
+
+    /// Returns the definition of the parameter with the given name.
+    pub fn get(&self, name: &str) -> Option<&ConfigParam> {
+        self.params.iter().find(|param| param.name == name)
+    }
+
+    /// Returns the size of the serialized configuration, including the
+    /// signature.
+    pub fn serialized_len(&self) -> usize {
+        self.params.last().map_or(Self::SIGNATURE_LEN, |param| {
+            param.offset + param.kind.size()
+        })
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{CommandReply, DecodeError};
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Range;
+
+use crate::frame::{FRAME_END, FRAME_START_LONG, FRAME_START_SHORT, claimed_len};
+use crate::{Command, CommandReply, DecodeCustom, DecodeError, Frame};
 
 /// A streaming decoder for VESC communication protocol.
 ///
@@ -8,18 +13,117 @@ use crate::{CommandReply, DecodeError};
 /// management.
 ///
 /// The decoder accepts data via [`feed`] and yields decoded frames through
-/// the [`Iterator`] interface.
+/// the [`Iterator`] returned by [`replies`]. Since replies may borrow from the
+/// internal buffer, they must be dropped before more data is fed. Peripherals
+/// such as a UART DMA can also write into the buffer directly, see
+/// [`write_buf`].
+///
+/// The buffer is used as a ring: data is appended after the buffered bytes,
+/// wrapping around to the front of the buffer once its end is reached, and
+/// frames are parsed in place. A frame that wraps around is copied into the
+/// free space to be parsed. Only if that is too small for it, when the buffer
+/// is more than half full, are the buffered bytes rotated to the front.
+///
+/// Data that cannot be decoded is skipped up to the next byte that can start
+/// a frame. A frame's length and checksum are validated before its payload is
+/// parsed, and a valid frame that cannot be decoded, such as one with an
+/// unknown packet ID, is dropped as a whole. How much was skipped and why is
+/// reported by [`stats`] and [`last_error`], to monitor the quality of the
+/// link.
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::{CommandReply, Decoder};
+///
+/// let mut decoder = Decoder::default();
+/// decoder.feed(&[2, 7, 50, 0, 0, 1, 128, 0, 0, 4, 210, 1, 176, 254, 22, 3]).unwrap();
+///
+/// for reply in decoder.replies() {
+///     if let CommandReply::GetValuesSelective(values) = reply {
+///         let rpm = values.rpm;
+///     }
+/// }
+/// ```
 ///
 /// [`feed`]: Self::feed
+/// [`replies`]: Self::replies
+/// [`write_buf`]: Self::write_buf
+/// [`stats`]: Self::stats
+/// [`last_error`]: Self::last_error
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Decoder<const BUFLEN: usize = 512> {
+pub struct Decoder<const BUFLEN: usize = { Command::MAX_FRAME_LEN }> {
     buf: [u8; BUFLEN],
-    wpos: usize,
-    rpos: usize,
+    state: State,
 }
 
-impl Default for Decoder<512> {
+/// A [`Decoder`] over caller-provided storage, such as a static DMA buffer.
+///
+/// It behaves like a [`Decoder`], but its capacity is the length of the
+/// storage, chosen at runtime, and it is cheap to move.
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::{CommandReply, SliceDecoder};
+///
+/// let mut storage = [0u8; 1024];
+/// let mut decoder = SliceDecoder::new(&mut storage);
+/// decoder.feed(&[2, 4, 36, 7, 8, 9, 96, 175, 3]).unwrap();
+///
+/// for reply in decoder.replies() {
+///     if let CommandReply::CustomAppData(data) = reply {
+///         assert_eq!(data, &[7, 8, 9]);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SliceDecoder<'a> {
+    buf: &'a mut [u8],
+    state: State,
+}
+
+/// The state shared by [`Decoder`] and [`SliceDecoder`], apart from their
+/// buffer.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct State {
+    // The position and length of the buffered data, which wraps around the
+    // end of the buffer.
+    start: usize,
+    len: usize,
+    // The position of `start` in the stream of fed bytes.
+    offset: usize,
+    stats: DecoderStats,
+    last_error: Option<DecodeError>,
+}
+
+/// Counters that describe the quality of the link a [`Decoder`] is fed from.
+///
+/// Counters wrap around on overflow.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DecoderStats {
+    /// The number of replies decoded.
+    pub frames_decoded: u32,
+
+    /// The number of bytes dropped while looking for the start of a frame,
+    /// or when the buffer was reset.
+    pub bytes_discarded: u32,
+
+    /// The number of frames dropped because of a checksum mismatch.
+    pub checksum_mismatches: u32,
+
+    /// The number of frames dropped because of an unknown packet ID.
+    pub unknown_packets: u32,
+
+    /// The number of times the buffer was reset because a frame did not fit.
+    pub buffer_resets: u32,
+}
+
+impl Default for Decoder {
     fn default() -> Self {
         Self::new()
     }
@@ -30,8 +134,7 @@ impl<const BUFLEN: usize> Decoder<BUFLEN> {
     pub fn new() -> Self {
         Self {
             buf: [0; BUFLEN],
-            rpos: 0,
-            wpos: 0,
+            state: State::default(),
         }
     }
 
@@ -41,34 +144,500 @@ impl<const BUFLEN: usize> Decoder<BUFLEN> {
     /// input length is consumed, the remaining bytes should be re-fed in the
     /// next call.
     ///
-    /// The decoder automatically manages buffer space by compacting processed
-    /// data and will reset if a single frame exceeds buffer capacity.
+    /// The decoder reuses the space of decoded data, and will reset if a
+    /// single frame exceeds buffer capacity.
     pub fn feed(&mut self, data: &[u8]) -> Result<usize, DecodeError> {
-        if data.len() > self.buf.len().saturating_sub(self.wpos) {
-            self.buf.copy_within(self.rpos..self.wpos, 0);
-            self.wpos = self.wpos.saturating_sub(self.rpos);
-            self.rpos = self.rpos.saturating_sub(self.rpos);
+        Ok(self.state.feed(&mut self.buf, data))
+    }
+
+    /// Returns the free space of the internal buffer, for a peripheral such
+    /// as a UART DMA to write into directly instead of going through
+    /// [`feed`](Self::feed). The written bytes are passed to the decoder with
+    /// [`commit`](Self::commit).
+    ///
+    /// This is the space right after the buffered bytes, up to the end of the
+    /// buffer, which may be less than all of the free space. Once it is used
+    /// up, the space at the front of the buffer is returned. Like `feed`, this
+    /// resets the decoder if a single frame fills the buffer.
+    ///
+    /// Written bytes must be committed before replies are decoded, as the
+    /// decoder may use the free space to parse a frame that wraps around.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vesc::Decoder;
+    ///
+    /// let mut decoder = Decoder::default();
+    ///
+    /// // A DMA transfer would write into the free space instead.
+    /// let free = decoder.write_buf();
+    /// free[..9].copy_from_slice(&[2, 4, 36, 7, 8, 9, 96, 175, 3]);
+    /// decoder.commit(9);
+    ///
+    /// assert_eq!(decoder.replies().count(), 1);
+    /// ```
+    pub fn write_buf(&mut self) -> &mut [u8] {
+        self.state.write_buf(&mut self.buf)
+    }
+
+    /// Passes the first `len` bytes written into the free space returned by
+    /// [`write_buf`](Self::write_buf) to the decoder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is larger than the free space.
+    pub fn commit(&mut self, len: usize) {
+        self.state.commit(&self.buf, len);
+    }
+
+    /// Decodes the next reply from the fed data, or returns `None` if more
+    /// data is needed.
+    ///
+    /// The decoder is not an [`Iterator`] itself, as its replies borrow from
+    /// its internal buffer. Use [`replies`](Self::replies) for an iterator.
+    // Not `Iterator::next`, whose items cannot borrow from the iterator.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<CommandReply<'_>> {
+        self.replies().next()
+    }
+
+    /// Returns an iterator over the replies decoded from the fed data.
+    ///
+    /// Replies are consumed as the iterator advances; once it returns `None`,
+    /// more data must be fed to decode further replies.
+    pub fn replies(&mut self) -> Replies<'_> {
+        self.state.replies(&mut self.buf)
+    }
+
+    /// Returns an iterator over the replies decoded from the fed data, which
+    /// also yields the frames that were skipped because of a checksum mismatch
+    /// or an unknown packet ID, see [`TryReplies`].
+    pub fn try_replies(&mut self) -> TryReplies<'_> {
+        TryReplies {
+            replies: self.replies(),
+        }
+    }
+
+    /// Returns the link-quality counters accumulated since the decoder was
+    /// created or the counters were last reset.
+    pub fn stats(&self) -> DecoderStats {
+        self.state.stats
+    }
+
+    /// Resets the link-quality counters and the last error.
+    pub fn reset_stats(&mut self) {
+        self.state.reset_stats();
+    }
+
+    /// Returns the last error that made the decoder discard data, if any.
+    pub fn last_error(&self) -> Option<&DecodeError> {
+        self.state.last_error.as_ref()
+    }
+}
+
+impl<'a, const BUFLEN: usize> IntoIterator for &'a mut Decoder<BUFLEN> {
+    type Item = CommandReply<'a>;
+    type IntoIter = Replies<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.replies()
+    }
+}
+
+impl<'a> SliceDecoder<'a> {
+    /// Creates a new decoder that buffers data in `buf`, whose previous
+    /// content is ignored.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            buf,
+            state: State::default(),
         }
+    }
+
+    /// The number of bytes the decoder can buffer, which is the length of the
+    /// storage it was created with.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Feeds new data into the decoder's buffer, see [`Decoder::feed`].
+    pub fn feed(&mut self, data: &[u8]) -> Result<usize, DecodeError> {
+        Ok(self.state.feed(self.buf, data))
+    }
+
+    /// Returns the free space of the buffer to write into directly, see
+    /// [`Decoder::write_buf`].
+    pub fn write_buf(&mut self) -> &mut [u8] {
+        self.state.write_buf(self.buf)
+    }
 
-        // Reset to prevent indefinite blocking if space reclamation cannot
-        // help. This occurs when a single VESC message exceed buffer capacity.
-        // Must never happen under normal circumstances unless internal buffer
-        // length is decreased down to inadequate value or modified VESC
-        // firmware is used.
-        if self.wpos == self.buf.len() {
-            self.rpos = 0;
-            self.wpos = 0;
+    /// Passes bytes written into the free space to the decoder, see
+    /// [`Decoder::commit`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is larger than the free space.
+    pub fn commit(&mut self, len: usize) {
+        self.state.commit(self.buf, len);
+    }
+
+    /// Decodes the next reply from the fed data, see [`Decoder::next`].
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<CommandReply<'_>> {
+        self.replies().next()
+    }
+
+    /// Returns an iterator over the replies decoded from the fed data, see
+    /// [`Decoder::replies`].
+    pub fn replies(&mut self) -> Replies<'_> {
+        self.state.replies(self.buf)
+    }
+
+    /// Returns an iterator over the replies decoded from the fed data, which
+    /// also yields the frames that were skipped, see [`TryReplies`].
+    pub fn try_replies(&mut self) -> TryReplies<'_> {
+        TryReplies {
+            replies: self.replies(),
         }
+    }
+
+    /// Returns the link-quality counters accumulated since the decoder was
+    /// created or the counters were last reset.
+    pub fn stats(&self) -> DecoderStats {
+        self.state.stats
+    }
+
+    /// Resets the link-quality counters and the last error.
+    pub fn reset_stats(&mut self) {
+        self.state.reset_stats();
+    }
+
+    /// Returns the last error that made the decoder discard data, if any.
+    pub fn last_error(&self) -> Option<&DecodeError> {
+        self.state.last_error.as_ref()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut SliceDecoder<'_> {
+    type Item = CommandReply<'a>;
+    type IntoIter = Replies<'a>;
 
-        let copied = data.len().min(self.buf.len().saturating_sub(self.wpos));
-        self.buf[self.wpos..self.wpos + copied].copy_from_slice(&data[..copied]);
-        self.wpos += copied;
-        Ok(copied)
+    fn into_iter(self) -> Self::IntoIter {
+        self.replies()
     }
 }
 
-impl<const BUFLEN: usize> core::iter::Iterator for Decoder<BUFLEN> {
-    type Item = CommandReply;
+impl State {
+    /// Copies `data` into the free space of `buf`. Returns the number of bytes
+    /// consumed.
+    fn feed(&mut self, buf: &mut [u8], data: &[u8]) -> usize {
+        self.reset_if_full(buf.len());
+
+        // The free space may wrap around the end of the buffer, in which case
+        // it takes two copies.
+        let mut fed = 0;
+        while fed < data.len() && self.len < buf.len() {
+            let free = self.free(buf.len());
+            let copied = free.len().min(data.len() - fed);
+            buf[free.start..free.start + copied].copy_from_slice(&data[fed..fed + copied]);
+            self.len += copied;
+            fed += copied;
+        }
+        fed
+    }
+
+    /// Returns the free space of `buf` right after the buffered bytes.
+    fn write_buf<'a>(&mut self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        self.reset_if_full(buf.len());
+        let free = self.free(buf.len());
+        &mut buf[free]
+    }
+
+    /// Appends the first `len` bytes of the free space to the buffered bytes.
+    fn commit(&mut self, buf: &[u8], len: usize) {
+        assert!(
+            len <= self.free(buf.len()).len(),
+            "committed more bytes than were free"
+        );
+        self.len += len;
+    }
+
+    /// Returns the range of the free space that follows the buffered bytes,
+    /// up to the end of the buffer or to the start of the buffered bytes.
+    fn free(&self, capacity: usize) -> Range<usize> {
+        let end = self.start + self.len;
+        if end < capacity {
+            end..capacity
+        } else {
+            end - capacity..self.start
+        }
+    }
+
+    /// Resets the buffer to prevent indefinite blocking if space reclamation
+    /// cannot help. This occurs when a single VESC message exceed buffer
+    /// capacity. Must never happen under normal circumstances unless internal
+    /// buffer length is decreased down to inadequate value or modified VESC
+    /// firmware is used.
+    fn reset_if_full(&mut self, capacity: usize) {
+        if self.len == capacity && self.len > 0 {
+            self.stats.buffer_resets = self.stats.buffer_resets.wrapping_add(1);
+            self.stats.bytes_discarded = self.stats.bytes_discarded.wrapping_add(self.len as u32);
+            self.offset = self.offset.wrapping_add(self.len);
+            self.start = 0;
+            self.len = 0;
+        }
+    }
+
+    fn replies<'a>(&'a mut self, buf: &'a mut [u8]) -> Replies<'a> {
+        let capacity = buf.len();
+
+        // A frame that wraps around the end of the buffer is copied into the
+        // free space to be parsed. That space is only sure to be large enough
+        // while the buffer is at most half full, or else the buffered bytes
+        // are rotated so that they no longer wrap around.
+        if self.start + self.len > capacity
+            && capacity - self.len < self.len.min(Command::MAX_FRAME_LEN)
+        {
+            buf.rotate_left(self.start);
+            self.start = 0;
+        }
+
+        let wrapped_len = (self.start + self.len).saturating_sub(capacity);
+        let (front, back) = buf.split_at_mut(self.start);
+        let (wrapped, free) = front.split_at_mut(wrapped_len);
+        Replies {
+            head: &back[..self.len - wrapped_len],
+            wrapped,
+            free,
+            pos: 0,
+            capacity,
+            state: self,
+            raw: false,
+        }
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = DecoderStats::default();
+        self.last_error = None;
+    }
+}
+
+/// An iterator over the replies buffered in a [`Decoder`].
+///
+/// Created by [`Decoder::replies`]. Replies borrow from the decoder's internal
+/// buffer.
+#[derive(Debug)]
+pub struct Replies<'a> {
+    // The buffered bytes up to the end of the buffer, and those that wrap
+    // around to its front.
+    head: &'a [u8],
+    wrapped: &'a [u8],
+    // The free space, which a frame that wraps around is copied into.
+    free: &'a mut [u8],
+    // The number of bytes consumed since the iterator was created.
+    pos: usize,
+    capacity: usize,
+    state: &'a mut State,
+    raw: bool,
+}
+
+impl<'a> Replies<'a> {
+    /// Returns an iterator that additionally yields replies with an
+    /// unsupported packet ID as [`CommandReply::Raw`], instead of skipping
+    /// them. See [`decode_raw`](crate::decode_raw).
+    pub fn with_raw(mut self) -> Self {
+        self.raw = true;
+        self
+    }
+
+    /// Returns an iterator that additionally decodes custom app data replies
+    /// into `T`.
+    ///
+    /// Custom app data that `T` fails to decode is passed through as a regular
+    /// [`CommandReply::CustomAppData`].
+    pub fn with_custom<T: DecodeCustom<'a>>(self) -> WithCustom<'a, T> {
+        WithCustom {
+            replies: self,
+            payload: PhantomData,
+        }
+    }
+
+    /// Decodes the next frame. Frames that are skipped because of a checksum
+    /// mismatch or an unknown packet ID are returned as errors if `report` is
+    /// set.
+    fn next_frame(&mut self, report: bool) -> Option<Result<CommandReply<'a>, FrameError<'a>>> {
+        loop {
+            let (head, wrapped) = self.pending();
+            let first = *head.first()?;
+            if first != FRAME_START_SHORT && first != FRAME_START_LONG {
+                self.skip(DecodeError::InvalidFrame);
+                continue;
+            }
+
+            let available = head.len() + wrapped.len();
+            let claimed = if head.len() >= 3 || wrapped.is_empty() {
+                claimed_len(head)
+            } else {
+                let mut header = [0; 3];
+                let len = available.min(3);
+                for (byte, &pending) in header.iter_mut().zip(head.iter().chain(wrapped)) {
+                    *byte = pending;
+                }
+                claimed_len(&header[..len])
+            };
+            let (bytes, len) = match claimed {
+                Ok(len) if len <= available => match self.frame_bytes(head, wrapped, len) {
+                    Ok(bytes) => (bytes, len),
+                    Err(err) => {
+                        self.skip(err);
+                        continue;
+                    }
+                },
+                // A false start byte may claim a frame that can never be
+                // buffered whole, which would block every frame after it.
+                Ok(len) if len <= self.capacity && available < self.capacity => return None,
+                Err(DecodeError::IncompleteData) if available < self.capacity => return None,
+                Ok(_) | Err(DecodeError::IncompleteData) => {
+                    self.skip(DecodeError::InvalidFrame);
+                    continue;
+                }
+                Err(err) => {
+                    self.skip(err);
+                    continue;
+                }
+            };
+
+            let frame = match Frame::parse(bytes) {
+                Ok(frame) => frame,
+                Err(err @ DecodeError::ChecksumMismatch { .. }) if report => {
+                    let error = self.frame_error(err.clone(), &bytes[..len]);
+                    self.skip(err);
+                    return Some(Err(error));
+                }
+                Err(err) => {
+                    self.skip(err);
+                    continue;
+                }
+            };
+
+            let reply = if self.raw {
+                frame.reply_or_raw()
+            } else {
+                frame.reply()
+            };
+            match reply {
+                Ok(reply) => {
+                    self.consume(len);
+                    self.state.stats.frames_decoded =
+                        self.state.stats.frames_decoded.wrapping_add(1);
+                    return Some(Ok(reply));
+                }
+                Err(err @ DecodeError::UnknownPacket { .. }) if report => {
+                    let error = self.frame_error(err.clone(), frame.as_bytes());
+                    self.discard(len, err);
+                    return Some(Err(error));
+                }
+                // The checksum is valid, so the whole frame is dropped rather
+                // than searched for frames within its payload.
+                Err(err) => self.discard(len, err),
+            }
+        }
+    }
+
+    /// Returns the pending bytes, split in two if they wrap around the end of
+    /// the buffer.
+    fn pending(&self) -> (&'a [u8], &'a [u8]) {
+        if self.pos < self.head.len() {
+            (&self.head[self.pos..], self.wrapped)
+        } else {
+            (&self.wrapped[self.pos - self.head.len()..], &[])
+        }
+    }
+
+    /// Returns the pending bytes that start with a frame of `len` bytes. If
+    /// the frame wraps around the end of the buffer, it is copied into the
+    /// free space first.
+    fn frame_bytes(
+        &mut self,
+        head: &'a [u8],
+        wrapped: &'a [u8],
+        len: usize,
+    ) -> Result<&'a [u8], DecodeError> {
+        if len <= head.len() {
+            return Ok(head);
+        }
+
+        // The end byte is checked first, so that false start bytes are not
+        // copied. Only frames with a checksum mismatch that are reported take
+        // up the free space for good, which may then run out.
+        let rest = &wrapped[..len - head.len()];
+        if rest.last() != Some(&FRAME_END) || self.free.len() < len {
+            return Err(DecodeError::InvalidFrame);
+        }
+        let (bytes, free) = mem::take(&mut self.free).split_at_mut(len);
+        bytes[..head.len()].copy_from_slice(head);
+        bytes[head.len()..].copy_from_slice(rest);
+        self.free = free;
+        Ok(bytes)
+    }
+
+    fn frame_error(&self, error: DecodeError, bytes: &'a [u8]) -> FrameError<'a> {
+        let start = self.state.offset;
+        FrameError {
+            error,
+            range: start..start.wrapping_add(bytes.len()),
+            bytes,
+        }
+    }
+
+    /// Discards the first pending byte along with any that follow it up to
+    /// the next one that can start a frame.
+    fn skip(&mut self, err: DecodeError) {
+        let (head, wrapped) = self.pending();
+        let len = head[1..]
+            .iter()
+            .chain(wrapped)
+            .position(|&byte| byte == FRAME_START_SHORT || byte == FRAME_START_LONG)
+            .map_or(head.len() + wrapped.len(), |pos| pos + 1);
+        self.discard(len, err);
+    }
+
+    fn discard(&mut self, len: usize, err: DecodeError) {
+        match err {
+            DecodeError::ChecksumMismatch { .. } => {
+                self.state.stats.checksum_mismatches =
+                    self.state.stats.checksum_mismatches.wrapping_add(1);
+            }
+            DecodeError::UnknownPacket { .. } => {
+                self.state.stats.unknown_packets = self.state.stats.unknown_packets.wrapping_add(1);
+            }
+            _ => (),
+        }
+        self.state.last_error = Some(err);
+        self.consume(len);
+        self.state.stats.bytes_discarded =
+            self.state.stats.bytes_discarded.wrapping_add(len as u32);
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.pos += len;
+        self.state.start += len;
+        if self.state.start >= self.capacity {
+            self.state.start -= self.capacity;
+        }
+        self.state.len -= len;
+        self.state.offset = self.state.offset.wrapping_add(len);
+        // Once everything is consumed, the whole buffer is free again without
+        // moving anything.
+        if self.state.len == 0 {
+            self.state.start = 0;
+        }
+    }
+}
+
+impl<'a> core::iter::Iterator for Replies<'a> {
+    type Item = CommandReply<'a>;
 
     /// Attempts to decode the next complete frame from the internal buffer.
     ///
@@ -76,17 +645,103 @@ impl<const BUFLEN: usize> core::iter::Iterator for Decoder<BUFLEN> {
     /// if more data is needed. Automatically handles frame synchronization by
     /// skipping corrupted data.
     fn next(&mut self) -> Option<Self::Item> {
-        while self.rpos < self.wpos {
-            match crate::decode(&self.buf[self.rpos..self.wpos]) {
-                Ok((consumed, reply)) => {
-                    self.rpos += consumed;
-                    return Some(reply);
-                }
-                Err(DecodeError::IncompleteData) => return None,
-                _ => (),
-            }
-            self.rpos += 1;
-        }
-        None
+        self.next_frame(false)?.ok()
+    }
+}
+
+/// A frame skipped by the decoder, as yielded by [`TryReplies`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameError<'a> {
+    /// Why the frame was skipped.
+    pub error: DecodeError,
+
+    /// The position of the frame in the stream of bytes fed to the decoder.
+    pub range: Range<usize>,
+
+    /// The raw frame, including its header, checksum and end byte.
+    pub bytes: &'a [u8],
+}
+
+/// An iterator over the replies buffered in a [`Decoder`] that also reports
+/// the frames it skips.
+///
+/// Created by [`Decoder::try_replies`]. Frames with a checksum mismatch or an
+/// unknown packet ID are yielded as errors, while the decoder resyncs as usual
+/// and carries on with the data that follows. Frames with an unknown packet
+/// ID are only reported once they are complete and their checksum is valid,
+/// which tells them apart from random data that looks like a frame start.
+/// Such data is skipped once the length it claims cannot fit into the buffer,
+/// or once it fills the buffer. Other malformed data is skipped silently, but
+/// still shows up in [`Decoder::stats`].
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::Decoder;
+///
+/// let mut decoder = Decoder::default();
+/// decoder.feed(&[2, 1, 200, 88, 68, 3]).unwrap();
+///
+/// for reply in decoder.try_replies() {
+///     match reply {
+///         Ok(reply) => println!("{reply:?}"),
+///         Err(err) => println!("skipped {:?}: {}", err.range, err.error),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TryReplies<'a> {
+    replies: Replies<'a>,
+}
+
+impl TryReplies<'_> {
+    /// Returns an iterator that yields replies with an unsupported packet ID
+    /// as [`CommandReply::Raw`], instead of reporting them as errors. See
+    /// [`Replies::with_raw`].
+    pub fn with_raw(mut self) -> Self {
+        self.replies.raw = true;
+        self
+    }
+}
+
+impl<'a> core::iter::Iterator for TryReplies<'a> {
+    type Item = Result<CommandReply<'a>, FrameError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.replies.next_frame(true)
+    }
+}
+
+/// A reply yielded by [`WithCustom`]: either a regular reply or a decoded
+/// custom payload.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CustomReply<'a, T> {
+    Reply(CommandReply<'a>),
+    Custom(T),
+}
+
+/// An iterator over the replies buffered in a [`Decoder`] that decodes custom
+/// app data into a user-defined [`DecodeCustom`] payload.
+///
+/// Created by [`Replies::with_custom`].
+#[derive(Debug)]
+pub struct WithCustom<'a, T> {
+    replies: Replies<'a>,
+    payload: PhantomData<T>,
+}
+
+impl<'a, T: DecodeCustom<'a>> core::iter::Iterator for WithCustom<'a, T> {
+    type Item = CustomReply<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.replies.next()? {
+            CommandReply::CustomAppData(data) => match T::decode(data) {
+                Ok(payload) => CustomReply::Custom(payload),
+                Err(_) => CustomReply::Reply(CommandReply::CustomAppData(data)),
+            },
+            reply => CustomReply::Reply(reply),
+        })
     }
 }
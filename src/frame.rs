@@ -0,0 +1,211 @@
+use crate::packer::{Packer, Unpacker};
+use crate::{CommandReply, DecodeError, EncodeError};
+
+pub(crate) const CRC16: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
+pub(crate) const FRAME_END: u8 = 3;
+pub(crate) const FRAME_START_SHORT: u8 = 2;
+pub(crate) const FRAME_START_LONG: u8 = 3;
+
+// The firmware drops packets with a payload longer than this, so no larger
+// frame is encoded, and anything claiming to be larger can only be a false
+// frame start.
+pub(crate) const PAYLOAD_MAX_LEN: usize = 512;
+
+/// A frame of the VESC serial protocol, checked but not interpreted.
+///
+/// A frame wraps a payload in a start byte and the payload length, followed
+/// by a CRC16 checksum and an end byte. [`Frame::parse`] validates all of
+/// these before the payload is looked at, so proxies, sniffers and routers
+/// can pass packets along without understanding them. The payload can still
+/// be interpreted afterwards with [`Frame::reply`].
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::Frame;
+///
+/// let mut buf = [0u8; 16];
+/// let len = Frame::write(&[200, 1], &mut buf).unwrap();
+///
+/// let frame = Frame::parse(&buf[..len]).unwrap();
+/// assert_eq!(frame.packet_id(), Some(200));
+/// assert_eq!(frame.payload(), &[200, 1]);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Frame<'a> {
+    bytes: &'a [u8],
+    header_len: usize,
+}
+
+impl<'a> Frame<'a> {
+    /// Parses the frame at the start of `buf`. Any data following the frame
+    /// is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecodeError::IncompleteData`] if `buf` does not contain the
+    /// whole frame yet, [`DecodeError::InvalidFrame`] for an invalid start
+    /// byte, payload length or end byte, and
+    /// [`DecodeError::ChecksumMismatch`] for a corrupted frame.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, DecodeError> {
+        let len = frame_len(buf)?;
+        let header_len = if buf[0] == FRAME_START_SHORT { 2 } else { 3 };
+
+        let mut unpacker = Unpacker::new(&buf[len - 3..len]);
+        let checksum_expected = unpacker.unpack_u16()?;
+        if unpacker.unpack_u8()? != FRAME_END {
+            return Err(DecodeError::InvalidFrame);
+        }
+        let checksum_actual = CRC16.checksum(&buf[header_len..len - 3]);
+        if checksum_actual != checksum_expected {
+            return Err(DecodeError::ChecksumMismatch {
+                expected: checksum_expected,
+                actual: checksum_actual,
+            });
+        }
+
+        Ok(Self {
+            bytes: &buf[..len],
+            header_len,
+        })
+    }
+
+    /// Writes a frame wrapping `payload` to `buf`. Returns the number of
+    /// bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EncodeError::BufferTooSmall`] if the frame does not fit into
+    /// `buf`, and [`EncodeError::PayloadTooLarge`] if the payload is longer
+    /// than the 512 bytes the firmware accepts.
+    pub fn write(payload: &[u8], buf: &mut [u8]) -> Result<usize, EncodeError> {
+        write_with(buf, |packer| {
+            packer.pack_with(|buf| {
+                buf.get_mut(..payload.len())
+                    .ok_or(EncodeError::BufferTooSmall)?
+                    .copy_from_slice(payload);
+                Ok(payload.len())
+            })
+        })
+    }
+
+    /// The whole frame, including its header, checksum and end byte.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The payload of the frame, starting with the packet ID.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.bytes[self.header_len..self.bytes.len() - 3]
+    }
+
+    /// The packet ID, which is the first byte of the payload, or `None` if
+    /// the payload is empty.
+    pub fn packet_id(&self) -> Option<u8> {
+        self.payload().first().copied()
+    }
+
+    /// Interprets the payload as a [`CommandReply`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecodeError::UnknownPacket`] for unsupported packet IDs, and
+    /// [`DecodeError::InvalidFrame`] if the payload is too short or too long
+    /// for its packet ID.
+    pub fn reply(&self) -> Result<CommandReply<'a>, DecodeError> {
+        let payload = self.payload();
+        let mut unpacker = Unpacker::new(payload);
+        match CommandReply::unpack_from(&mut unpacker) {
+            Ok(_) if unpacker.pos != payload.len() => Err(DecodeError::InvalidFrame),
+            Ok(reply) => Ok(reply),
+            Err(DecodeError::IncompleteData) => Err(DecodeError::InvalidFrame),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Interprets the payload like [`reply`](Self::reply), but returns
+    /// unsupported packets as [`CommandReply::Raw`].
+    pub(crate) fn reply_or_raw(&self) -> Result<CommandReply<'a>, DecodeError> {
+        match self.reply() {
+            Err(DecodeError::UnknownPacket { id }) => Ok(CommandReply::Raw {
+                id,
+                payload: &self.payload()[1..],
+            }),
+            result => result,
+        }
+    }
+}
+
+/// Writes a frame to `buf` whose payload is packed by `pack_payload`. Returns
+/// the number of bytes written.
+pub(crate) fn write_with(
+    buf: &mut [u8],
+    pack_payload: impl FnOnce(&mut Packer) -> Result<(), EncodeError>,
+) -> Result<usize, EncodeError> {
+    let mut packer = Packer::new(buf);
+    packer.pack_u8(FRAME_START_SHORT)?;
+    packer.pack_u8(0)?;
+    pack_payload(&mut packer)?;
+    let payload_len = packer.pos - 2;
+    if payload_len > PAYLOAD_MAX_LEN {
+        return Err(EncodeError::PayloadTooLarge);
+    }
+
+    // The payload length isn't known until the command is packed, so the
+    // header is written optimistically for a short frame. Payloads that don't
+    // fit a one-byte length need a long frame, whose header is one byte
+    // longer, so the payload is shifted to make room for it.
+    let payload_start = if payload_len > u8::MAX as usize {
+        packer.pack_u8(0)?;
+        packer.buf.copy_within(2..2 + payload_len, 3);
+        packer.buf[0] = FRAME_START_LONG;
+        packer.buf[1..3].copy_from_slice(&(payload_len as u16).to_be_bytes());
+        3
+    } else {
+        packer.buf[1] = payload_len as u8;
+        2
+    };
+
+    let payload = &packer.buf[payload_start..payload_start + payload_len];
+    packer.pack_u16(CRC16.checksum(payload))?;
+    packer.pack_u8(FRAME_END)?;
+    Ok(packer.pos)
+}
+
+/// Returns the length of the header of a frame carrying `payload_len` bytes.
+pub(crate) fn header_len(payload_len: usize) -> Result<usize, EncodeError> {
+    if payload_len > PAYLOAD_MAX_LEN {
+        Err(EncodeError::PayloadTooLarge)
+    } else if payload_len > u8::MAX as usize {
+        Ok(3)
+    } else {
+        Ok(2)
+    }
+}
+
+/// Returns the length of the frame at the start of `buf`, judging by its
+/// header alone, once all of it is available.
+pub(crate) fn frame_len(buf: &[u8]) -> Result<usize, DecodeError> {
+    let len = claimed_len(buf)?;
+    if buf.len() < len {
+        return Err(DecodeError::IncompleteData);
+    }
+    Ok(len)
+}
+
+/// Returns the length the header at the start of `buf` claims for its frame,
+/// whether or not all of the frame is available yet.
+pub(crate) fn claimed_len(buf: &[u8]) -> Result<usize, DecodeError> {
+    let mut unpacker = Unpacker::new(buf);
+
+    let payload_len = match unpacker.unpack_u8()? {
+        FRAME_START_SHORT => unpacker.unpack_u8()? as usize,
+        FRAME_START_LONG => unpacker.unpack_u16()? as usize,
+        _ => return Err(DecodeError::InvalidFrame),
+    };
+    if payload_len > PAYLOAD_MAX_LEN {
+        return Err(DecodeError::InvalidFrame);
+    }
+    Ok(unpacker.pos + payload_len + 3)
+}
@@ -28,13 +28,34 @@
 //!     _ => (),
 //! }
 //! ```
+//!
+//! Proxies and other tools that pass frames along without interpreting them
+//! can use [`Frame`] instead of [`decode`].
+//!
+//! Hosts on the CAN bus can talk to controllers directly with the [`can`]
+//! module instead.
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod can;
 mod command;
+mod custom_config;
 mod decoder;
+mod frame;
+mod owned;
 mod packer;
+mod values;
 
 pub use command::{
-    Command, CommandReply, DecodeError, EncodeError, Values, ValuesMask, decode, encode,
+    Command, CommandReply, DecodeCustom, DecodeError, EncodeCustom, EncodeError, FileEntries,
+    FileEntry, FileList, LispBinding, LispBindings, LispStats, decode, decode_raw, encode,
+    encode_custom, encode_to,
+};
+#[cfg(feature = "std")]
+pub use custom_config::{ConfigParam, CustomConfigParams};
+pub use custom_config::{ConfigParamKind, ConfigValue, CustomConfigError, CustomConfigXmlDownload};
+pub use decoder::{
+    CustomReply, Decoder, DecoderStats, FrameError, Replies, SliceDecoder, TryReplies, WithCustom,
 };
-pub use decoder::Decoder;
+pub use frame::Frame;
+pub use owned::{InlineBytes, InlineStr, OwnedCommand, OwnedCommandError, OwnedCommandKind};
+pub use values::{Values, ValuesMask};
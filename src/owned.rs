@@ -0,0 +1,469 @@
+use crate::{Command, EncodeError, ValuesMask, encode};
+
+/// Errors that can occur when converting a [`Command`] into an
+/// [`OwnedCommand`].
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum OwnedCommandError {
+    #[error("the command carries more data than the owned command can hold")]
+    TooLong,
+
+    #[error("the command is forwarded over CAN more often than the owned command allows")]
+    TooDeep,
+
+    #[error("the CAN frame has more than 8 data bytes")]
+    InvalidCanFrame,
+}
+
+/// The commands an [`OwnedCommand`] can carry, which mirror the variants of
+/// [`Command`].
+///
+/// Data that a [`Command`] borrows, such as paths, code or custom app data,
+/// is copied into inline buffers of up to `LEN` bytes each.
+///
+/// Forwarding over CAN is not a variant here, but part of the
+/// [`OwnedCommand`] that wraps the command.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OwnedCommandKind<const LEN: usize = 64> {
+    /// See [`Command::GetValues`].
+    GetValues,
+
+    /// See [`Command::SetCurrent`].
+    SetCurrent(f32),
+
+    /// See [`Command::SetRpm`].
+    SetRpm(i32),
+
+    /// See [`Command::SetHandbrake`].
+    SetHandbrake(f32),
+
+    /// See [`Command::GetValuesSelective`].
+    GetValuesSelective(ValuesMask),
+
+    /// See [`Command::LispReadCode`].
+    LispReadCode { len: u32, offset: u32 },
+
+    /// See [`Command::LispWriteCode`].
+    LispWriteCode { offset: u32, data: InlineBytes<LEN> },
+
+    /// See [`Command::LispEraseCode`].
+    LispEraseCode(u32),
+
+    /// See [`Command::LispSetRunning`].
+    LispSetRunning(bool),
+
+    /// See [`Command::LispGetStats`].
+    LispGetStats,
+
+    /// See [`Command::LispReplCmd`].
+    LispReplCmd(InlineStr<LEN>),
+
+    /// See [`Command::LispStreamCode`].
+    LispStreamCode {
+        offset: u32,
+        total_len: u32,
+        restart: bool,
+        data: InlineBytes<LEN>,
+    },
+
+    /// See [`Command::FileList`].
+    FileList {
+        path: InlineStr<LEN>,
+        from: Option<InlineStr<LEN>>,
+    },
+
+    /// See [`Command::FileRead`].
+    FileRead { path: InlineStr<LEN>, offset: u32 },
+
+    /// See [`Command::FileWrite`].
+    FileWrite {
+        path: InlineStr<LEN>,
+        offset: u32,
+        total_len: u32,
+        data: InlineBytes<LEN>,
+    },
+
+    /// See [`Command::FileMkdir`].
+    FileMkdir(InlineStr<LEN>),
+
+    /// See [`Command::FileRemove`].
+    FileRemove(InlineStr<LEN>),
+
+    /// See [`Command::GetCustomConfigXml`].
+    GetCustomConfigXml { index: u8, len: u32, offset: u32 },
+
+    /// See [`Command::GetCustomConfig`].
+    GetCustomConfig(u8),
+
+    /// See [`Command::GetCustomConfigDefault`].
+    GetCustomConfigDefault(u8),
+
+    /// See [`Command::SetCustomConfig`].
+    SetCustomConfig { index: u8, data: InlineBytes<LEN> },
+
+    /// See [`Command::CustomAppData`].
+    CustomAppData(InlineBytes<LEN>),
+
+    /// See [`Command::CanFwdFrame`]. Only the first `len` bytes of `data` are
+    /// sent.
+    CanFwdFrame {
+        id: u32,
+        extended: bool,
+        data: [u8; 8],
+        len: u8,
+    },
+}
+
+/// Up to `LEN` bytes stored inline, as carried by an [`OwnedCommandKind`].
+#[derive(Debug, Copy, Clone)]
+pub struct InlineBytes<const LEN: usize> {
+    buf: [u8; LEN],
+    len: usize,
+}
+
+impl<const LEN: usize> InlineBytes<LEN> {
+    /// The stored bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl<const LEN: usize> TryFrom<&[u8]> for InlineBytes<LEN> {
+    type Error = OwnedCommandError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let mut buf = [0; LEN];
+        buf.get_mut(..data.len())
+            .ok_or(OwnedCommandError::TooLong)?
+            .copy_from_slice(data);
+        Ok(Self {
+            buf,
+            len: data.len(),
+        })
+    }
+}
+
+impl<const LEN: usize> PartialEq for InlineBytes<LEN> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const LEN: usize> Eq for InlineBytes<LEN> {}
+
+#[cfg(feature = "defmt")]
+impl<const LEN: usize> defmt::Format for InlineBytes<LEN> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=[u8]}", self.as_bytes())
+    }
+}
+
+/// A string of up to `LEN` bytes stored inline, as carried by an
+/// [`OwnedCommandKind`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InlineStr<const LEN: usize>(InlineBytes<LEN>);
+
+impl<const LEN: usize> InlineStr<LEN> {
+    /// The stored string.
+    pub fn as_str(&self) -> &str {
+        // SAFETY: the bytes were copied whole from a `str`.
+        unsafe { core::str::from_utf8_unchecked(self.0.as_bytes()) }
+    }
+}
+
+impl<const LEN: usize> TryFrom<&str> for InlineStr<LEN> {
+    type Error = OwnedCommandError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.as_bytes().try_into().map(Self)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<const LEN: usize> defmt::Format for InlineStr<LEN> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=str}", self.as_str())
+    }
+}
+
+/// A [`Command`] that owns its data, so that it can be stored in queues or
+/// sent across channels.
+///
+/// [`Command::ForwardCan`] borrows the command it forwards, which ties a
+/// command to the stack frame that built it. An owned command instead keeps
+/// the controller IDs it is forwarded through, up to `DEPTH` of them, next to
+/// the command itself. The data a command carries is stored inline as well,
+/// up to `LEN` bytes per field, see [`OwnedCommandKind`].
+///
+/// # Example
+///
+/// ```rust
+/// use vesc::{Command, OwnedCommand, OwnedCommandKind};
+///
+/// let command = OwnedCommand::<1>::new(OwnedCommandKind::SetRpm(1500))
+///     .forward_can(3)
+///     .unwrap();
+///
+/// let mut buf = [0u8; 16];
+/// let len = command.encode(&mut buf).unwrap();
+///
+/// let mut expected = [0u8; 16];
+/// vesc::encode(Command::ForwardCan(3, &Command::SetRpm(1500)), &mut expected).unwrap();
+/// assert_eq!(buf[..len], expected[..len]);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OwnedCommand<const DEPTH: usize = 1, const LEN: usize = 64> {
+    // The controller IDs the command is forwarded through, outermost first.
+    can_path: [u8; DEPTH],
+    depth: usize,
+    command: OwnedCommandKind<LEN>,
+}
+
+impl<const DEPTH: usize, const LEN: usize> OwnedCommand<DEPTH, LEN> {
+    /// Creates a command that is sent to the controller it is encoded for,
+    /// without forwarding.
+    pub fn new(command: OwnedCommandKind<LEN>) -> Self {
+        Self {
+            can_path: [0; DEPTH],
+            depth: 0,
+            command,
+        }
+    }
+
+    /// Forwards the command over CAN to the controller with the given ID, on
+    /// top of any forwarding it already has.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OwnedCommandError::TooDeep`] if the command is already
+    /// forwarded `DEPTH` times.
+    pub fn forward_can(mut self, controller_id: u8) -> Result<Self, OwnedCommandError> {
+        if self.depth == DEPTH {
+            return Err(OwnedCommandError::TooDeep);
+        }
+        self.can_path.copy_within(..self.depth, 1);
+        self.can_path[0] = controller_id;
+        self.depth += 1;
+        Ok(self)
+    }
+
+    /// The controller IDs the command is forwarded through, outermost first.
+    pub fn can_path(&self) -> &[u8] {
+        &self.can_path[..self.depth]
+    }
+
+    /// The command that is eventually executed.
+    pub fn command(&self) -> &OwnedCommandKind<LEN> {
+        &self.command
+    }
+
+    /// Calls `f` with the equivalent [`Command`], whose forwarding is built
+    /// on the stack for the duration of the call.
+    pub fn with_command<R>(&self, f: impl FnOnce(Command<'_>) -> R) -> R {
+        let command = match &self.command {
+            OwnedCommandKind::GetValues => Command::GetValues,
+            OwnedCommandKind::SetCurrent(current) => Command::SetCurrent(*current),
+            OwnedCommandKind::SetRpm(rpm) => Command::SetRpm(*rpm),
+            OwnedCommandKind::SetHandbrake(current) => Command::SetHandbrake(*current),
+            OwnedCommandKind::GetValuesSelective(mask) => Command::GetValuesSelective(*mask),
+            OwnedCommandKind::LispReadCode { len, offset } => Command::LispReadCode {
+                len: *len,
+                offset: *offset,
+            },
+            OwnedCommandKind::LispWriteCode { offset, data } => Command::LispWriteCode {
+                offset: *offset,
+                data: data.as_bytes(),
+            },
+            OwnedCommandKind::LispEraseCode(size) => Command::LispEraseCode(*size),
+            OwnedCommandKind::LispSetRunning(running) => Command::LispSetRunning(*running),
+            OwnedCommandKind::LispGetStats => Command::LispGetStats,
+            OwnedCommandKind::LispReplCmd(expr) => Command::LispReplCmd(expr.as_str()),
+            OwnedCommandKind::LispStreamCode {
+                offset,
+                total_len,
+                restart,
+                data,
+            } => Command::LispStreamCode {
+                offset: *offset,
+                total_len: *total_len,
+                restart: *restart,
+                data: data.as_bytes(),
+            },
+            OwnedCommandKind::FileList { path, from } => Command::FileList {
+                path: path.as_str(),
+                from: from.as_ref().map(InlineStr::as_str),
+            },
+            OwnedCommandKind::FileRead { path, offset } => Command::FileRead {
+                path: path.as_str(),
+                offset: *offset,
+            },
+            OwnedCommandKind::FileWrite {
+                path,
+                offset,
+                total_len,
+                data,
+            } => Command::FileWrite {
+                path: path.as_str(),
+                offset: *offset,
+                total_len: *total_len,
+                data: data.as_bytes(),
+            },
+            OwnedCommandKind::FileMkdir(path) => Command::FileMkdir(path.as_str()),
+            OwnedCommandKind::FileRemove(path) => Command::FileRemove(path.as_str()),
+            OwnedCommandKind::GetCustomConfigXml { index, len, offset } => {
+                Command::GetCustomConfigXml {
+                    index: *index,
+                    len: *len,
+                    offset: *offset,
+                }
+            }
+            OwnedCommandKind::GetCustomConfig(index) => Command::GetCustomConfig(*index),
+            OwnedCommandKind::GetCustomConfigDefault(index) => {
+                Command::GetCustomConfigDefault(*index)
+            }
+            OwnedCommandKind::SetCustomConfig { index, data } => Command::SetCustomConfig {
+                index: *index,
+                data: data.as_bytes(),
+            },
+            OwnedCommandKind::CustomAppData(data) => Command::CustomAppData(data.as_bytes()),
+            OwnedCommandKind::CanFwdFrame {
+                id,
+                extended,
+                data,
+                len,
+            } => Command::CanFwdFrame {
+                id: *id,
+                extended: *extended,
+                data: &data[..(*len as usize).min(data.len())],
+            },
+        };
+        forward(self.can_path(), command, f)
+    }
+
+    /// Encodes the command into a byte buffer, see [`encode`].
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        self.with_command(|command| encode(command, buf))
+    }
+
+    /// Returns the length of the frame the command is encoded into, see
+    /// [`Command::encoded_len`].
+    pub fn encoded_len(&self) -> Result<usize, EncodeError> {
+        self.with_command(|command| command.encoded_len())
+    }
+}
+
+impl<const DEPTH: usize, const LEN: usize> From<OwnedCommandKind<LEN>>
+    for OwnedCommand<DEPTH, LEN>
+{
+    fn from(command: OwnedCommandKind<LEN>) -> Self {
+        Self::new(command)
+    }
+}
+
+impl<const DEPTH: usize, const LEN: usize> TryFrom<Command<'_>> for OwnedCommand<DEPTH, LEN> {
+    type Error = OwnedCommandError;
+
+    fn try_from(mut command: Command<'_>) -> Result<Self, Self::Error> {
+        let mut can_path = [0; DEPTH];
+        let mut depth = 0;
+        while let Command::ForwardCan(controller_id, inner) = command {
+            *can_path.get_mut(depth).ok_or(OwnedCommandError::TooDeep)? = controller_id;
+            depth += 1;
+            command = *inner;
+        }
+
+        let command = match command {
+            Command::GetValues => OwnedCommandKind::GetValues,
+            Command::SetCurrent(current) => OwnedCommandKind::SetCurrent(current),
+            Command::SetRpm(rpm) => OwnedCommandKind::SetRpm(rpm),
+            Command::SetHandbrake(current) => OwnedCommandKind::SetHandbrake(current),
+            Command::GetValuesSelective(mask) => OwnedCommandKind::GetValuesSelective(mask),
+            Command::LispReadCode { len, offset } => OwnedCommandKind::LispReadCode { len, offset },
+            Command::LispWriteCode { offset, data } => OwnedCommandKind::LispWriteCode {
+                offset,
+                data: data.try_into()?,
+            },
+            Command::LispEraseCode(size) => OwnedCommandKind::LispEraseCode(size),
+            Command::LispSetRunning(running) => OwnedCommandKind::LispSetRunning(running),
+            Command::LispGetStats => OwnedCommandKind::LispGetStats,
+            Command::LispReplCmd(expr) => OwnedCommandKind::LispReplCmd(expr.try_into()?),
+            Command::LispStreamCode {
+                offset,
+                total_len,
+                restart,
+                data,
+            } => OwnedCommandKind::LispStreamCode {
+                offset,
+                total_len,
+                restart,
+                data: data.try_into()?,
+            },
+            Command::FileList { path, from } => OwnedCommandKind::FileList {
+                path: path.try_into()?,
+                from: from.map(TryInto::try_into).transpose()?,
+            },
+            Command::FileRead { path, offset } => OwnedCommandKind::FileRead {
+                path: path.try_into()?,
+                offset,
+            },
+            Command::FileWrite {
+                path,
+                offset,
+                total_len,
+                data,
+            } => OwnedCommandKind::FileWrite {
+                path: path.try_into()?,
+                offset,
+                total_len,
+                data: data.try_into()?,
+            },
+            Command::FileMkdir(path) => OwnedCommandKind::FileMkdir(path.try_into()?),
+            Command::FileRemove(path) => OwnedCommandKind::FileRemove(path.try_into()?),
+            Command::GetCustomConfigXml { index, len, offset } => {
+                OwnedCommandKind::GetCustomConfigXml { index, len, offset }
+            }
+            Command::GetCustomConfig(index) => OwnedCommandKind::GetCustomConfig(index),
+            Command::GetCustomConfigDefault(index) => {
+                OwnedCommandKind::GetCustomConfigDefault(index)
+            }
+            Command::SetCustomConfig { index, data } => OwnedCommandKind::SetCustomConfig {
+                index,
+                data: data.try_into()?,
+            },
+            Command::CustomAppData(data) => OwnedCommandKind::CustomAppData(data.try_into()?),
+            Command::CanFwdFrame { id, extended, data } => {
+                let mut buf = [0; 8];
+                buf.get_mut(..data.len())
+                    .ok_or(OwnedCommandError::InvalidCanFrame)?
+                    .copy_from_slice(data);
+                OwnedCommandKind::CanFwdFrame {
+                    id,
+                    extended,
+                    data: buf,
+                    len: data.len() as u8,
+                }
+            }
+            Command::ForwardCan(..) => unreachable!("forwarding was unwrapped above"),
+        };
+
+        Ok(Self {
+            can_path,
+            depth,
+            command,
+        })
+    }
+}
+
+/// Wraps `command` in a [`Command::ForwardCan`] for each controller ID in
+/// `can_path`, outermost first, and passes the result to `f`.
+fn forward<R>(can_path: &[u8], command: Command<'_>, f: impl FnOnce(Command<'_>) -> R) -> R {
+    match can_path.split_last() {
+        Some((&controller_id, outer)) => {
+            forward(outer, Command::ForwardCan(controller_id, &command), f)
+        }
+        None => f(command),
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{DecodeError, EncodeError};
 
+/// Receives the bytes packed by a [`Packer`] created with [`Packer::with_sink`].
+pub type Sink<'a> = &'a mut dyn FnMut(&[u8]);
+
 /// A utility for structured serialization into a pre-allocated buffer. It's
 /// designed for the VESC communication protocol, which requires big-endian byte
 /// order and represents floating-point values as scaled integers. This struct
@@ -8,12 +11,47 @@ use crate::{DecodeError, EncodeError};
 pub struct Packer<'a> {
     pub buf: &'a mut [u8],
     pub pos: usize,
+    sink: Option<Sink<'a>>,
+}
+
+/// Scales `value` and rounds it to the nearest integer, with halfway cases
+/// rounded away from zero. Fails unless the result lies within `min..=max`.
+fn scale_to_fixed(value: f32, scale: f32, min: f64, max: f64) -> Result<f64, EncodeError> {
+    // The product of two `f32`s is exact in an `f64`, so adding a half
+    // cannot round it up a second time.
+    let scaled = f64::from(value) * f64::from(scale);
+    let rounded = if scaled < 0.0 {
+        -((0.5 - scaled) as i64 as f64)
+    } else {
+        (scaled + 0.5) as i64 as f64
+    };
+    // NaN and infinities are never within range.
+    if scaled.is_finite() && (min..=max).contains(&rounded) {
+        Ok(rounded)
+    } else {
+        Err(EncodeError::ValueOutOfRange)
+    }
 }
 
 impl<'a> Packer<'a> {
     #[inline]
     pub fn new(buf: &'a mut [u8]) -> Self {
-        Self { buf, pos: 0 }
+        Self {
+            buf,
+            pos: 0,
+            sink: None,
+        }
+    }
+
+    /// Creates a packer that passes the packed bytes to `sink` instead of
+    /// storing them, while still counting them in `pos`.
+    #[inline]
+    pub fn with_sink(sink: Sink<'a>) -> Self {
+        Self {
+            buf: &mut [],
+            pos: 0,
+            sink: Some(sink),
+        }
     }
 
     #[inline]
@@ -31,18 +69,98 @@ impl<'a> Packer<'a> {
         self.pack(&value.to_be_bytes())
     }
 
+    #[inline]
+    pub fn pack_i16(&mut self, value: i16) -> Result<(), EncodeError> {
+        self.pack(&value.to_be_bytes())
+    }
+
     #[inline]
     pub fn pack_u8(&mut self, value: u8) -> Result<(), EncodeError> {
         self.pack(&value.to_be_bytes())
     }
 
+    #[inline]
+    pub fn pack_i8(&mut self, value: i8) -> Result<(), EncodeError> {
+        self.pack(&value.to_be_bytes())
+    }
+
+    /// Packs a float as a 32-bit integer scaled by `scale`, rounded to the
+    /// nearest integer as the firmware's `buffer_append_float32` does.
     #[inline]
     pub fn pack_f32(&mut self, value: f32, scale: f32) -> Result<(), EncodeError> {
-        self.pack_i32((value * scale) as i32)
+        let value = scale_to_fixed(value, scale, i32::MIN.into(), i32::MAX.into())?;
+        self.pack_i32(value as i32)
+    }
+
+    /// Packs a float as a 16-bit integer scaled by `scale`, rounded to the
+    /// nearest integer as the firmware's `buffer_append_float16` does.
+    #[inline]
+    pub fn pack_f16(&mut self, value: f32, scale: f32) -> Result<(), EncodeError> {
+        let value = scale_to_fixed(value, scale, i16::MIN.into(), i16::MAX.into())?;
+        self.pack_i16(value as i16)
+    }
+
+    /// Packs a float the way the firmware's `buffer_append_float32_auto` does.
+    /// Normal values keep their IEEE-754 representation, while values below
+    /// 1.5e-38 in magnitude, including subnormals and negative zero, are
+    /// flushed to positive zero.
+    ///
+    /// The firmware has no encoding for NaN and infinities, so they are
+    /// rejected rather than sent as an arbitrary value.
+    #[inline]
+    pub fn pack_f32_auto(&mut self, value: f32) -> Result<(), EncodeError> {
+        if !value.is_finite() {
+            return Err(EncodeError::ValueOutOfRange);
+        }
+        // The firmware is built with `-fsingle-precision-constant`, so the
+        // threshold is a float there as well.
+        let value = if value.abs() < 1.5e-38 { 0.0 } else { value };
+        self.pack_u32(value.to_bits())
+    }
+
+    #[inline]
+    pub fn pack_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.pack(bytes)
+    }
+
+    /// Packs a string followed by a NUL terminator, as the firmware expects.
+    /// Strings that contain a NUL byte are rejected, as the firmware would
+    /// cut them short.
+    #[inline]
+    pub fn pack_str(&mut self, value: &str) -> Result<(), EncodeError> {
+        if value.as_bytes().contains(&0) {
+            return Err(EncodeError::InvalidString);
+        }
+        self.pack(value.as_bytes())?;
+        self.pack_u8(0)
+    }
+
+    /// Lets `pack` write directly into the remaining buffer space. It must
+    /// return the number of bytes written.
+    ///
+    /// Packers over a sink have no buffer space to lend, so this fails with
+    /// [`EncodeError::BufferTooSmall`] for them.
+    #[inline]
+    pub fn pack_with(
+        &mut self,
+        pack: impl FnOnce(&mut [u8]) -> Result<usize, EncodeError>,
+    ) -> Result<(), EncodeError> {
+        if self.sink.is_some() {
+            return Err(EncodeError::BufferTooSmall);
+        }
+        let written = pack(&mut self.buf[self.pos..])?;
+        self.pos += written;
+        Ok(())
     }
 
     #[inline]
     fn pack(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        if let Some(sink) = &mut self.sink {
+            sink(bytes);
+            self.pos += bytes.len();
+            return Ok(());
+        }
+
         let need = bytes.len();
         if self.pos + need > self.buf.len() {
             return Err(EncodeError::BufferTooSmall);
@@ -94,6 +212,11 @@ impl<'a> Unpacker<'a> {
         Ok(u8::from_be_bytes(self.consume(1)?.try_into().unwrap()))
     }
 
+    #[inline]
+    pub fn unpack_i8(&mut self) -> Result<i8, DecodeError> {
+        Ok(i8::from_be_bytes(self.consume(1)?.try_into().unwrap()))
+    }
+
     #[inline]
     pub fn unpack_f32(&mut self, scale: f32) -> Result<f32, DecodeError> {
         Ok(self.unpack_i32()? as f32 / scale)
@@ -104,8 +227,46 @@ impl<'a> Unpacker<'a> {
         Ok(self.unpack_i16()? as f32 / scale)
     }
 
+    /// Unpacks a float encoded with the firmware's `buffer_append_float32_auto`,
+    /// which stores the sign, exponent and significand in IEEE-754 layout but
+    /// without scaling. Subnormal values are decoded the way the firmware's
+    /// `ldexpf` based decoder does.
+    #[inline]
+    pub fn unpack_f32_auto(&mut self) -> Result<f32, DecodeError> {
+        let bits = self.unpack_u32()?;
+        let exponent = (bits >> 23) & 0xff;
+        let significand = bits & 0x7f_ffff;
+
+        let value = match exponent {
+            0 if significand == 0 => 0.0,
+            0 => ((0.5 + significand as f64 / 16_777_216.0) * f32::MIN_POSITIVE as f64) as f32,
+            0xff => f32::INFINITY,
+            _ => f32::from_bits(bits & 0x7fff_ffff),
+        };
+        Ok(if bits & (1 << 31) != 0 { -value } else { value })
+    }
+
+    /// Unpacks everything left in the buffer.
+    #[inline]
+    pub fn unpack_remaining(&mut self) -> &'a [u8] {
+        let remaining = &self.buf[self.pos..];
+        self.pos = self.buf.len();
+        remaining
+    }
+
+    /// Unpacks a NUL-terminated UTF-8 string, consuming the terminator.
+    #[inline]
+    pub fn unpack_str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.buf[self.pos..]
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or(DecodeError::IncompleteData)?;
+        let bytes = self.consume(len + 1)?;
+        core::str::from_utf8(&bytes[..len]).map_err(|_| DecodeError::InvalidFrame)
+    }
+
     #[inline]
-    fn consume(&mut self, amount: usize) -> Result<&[u8], DecodeError> {
+    fn consume(&mut self, amount: usize) -> Result<&'a [u8], DecodeError> {
         if self.pos + amount > self.buf.len() {
             return Err(DecodeError::IncompleteData);
         }
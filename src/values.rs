@@ -0,0 +1,209 @@
+use bitflags::bitflags;
+
+use crate::command::CommandId;
+use crate::frame::write_with;
+use crate::packer::{Packer, Unpacker};
+use crate::{DecodeError, EncodeError};
+
+/// Generates a telemetry struct, the mask that selects its fields, and their
+/// packing and unpacking from a single table.
+///
+/// Each row of the table is a mask flag with its bit, followed by the fields
+/// it selects in wire order. A field is a name and a wire type, with a scale
+/// for fixed-point floats: `f16 / 10.0` is an `f32` sent as an `i16` holding
+/// ten times the value.
+macro_rules! telemetry {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident;
+
+        $(#[$mask_meta:meta])*
+        pub struct $mask:ident;
+
+        $(
+            $flag:ident = $bit:literal {
+                $($field:ident: $kind:ident $(/ $scale:literal)?),+ $(,)?
+            }
+        )+
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, Default, PartialEq)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub struct $name {
+            $($(pub $field: telemetry!(@type $kind),)+)+
+        }
+
+        $(#[$mask_meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub struct $mask(u32);
+
+        bitflags! {
+            impl $mask: u32 {
+                $(const $flag = 1 << $bit;)+
+            }
+        }
+
+        impl $name {
+            /// Unpacks the fields selected by `mask`, leaving the others at
+            /// their default.
+            pub(crate) fn unpack_from(
+                unpacker: &mut Unpacker,
+                mask: $mask,
+            ) -> Result<Self, DecodeError> {
+                let mut values = Self::default();
+                $(
+                    if mask.contains($mask::$flag) {
+                        $(values.$field = telemetry!(@unpack unpacker, $kind $(, $scale)?);)+
+                    }
+                )+
+                Ok(values)
+            }
+
+            /// Unpacks the fields in table order until the payload ends. Older
+            /// firmware sends fewer fields, which are left at their default,
+            /// and fields appended by newer firmware are skipped.
+            pub(crate) fn unpack_available(unpacker: &mut Unpacker) -> Result<Self, DecodeError> {
+                let mut values = Self::default();
+                $(
+                    if unpacker.pos == unpacker.buf.len() {
+                        return Ok(values);
+                    }
+                    $(values.$field = telemetry!(@unpack unpacker, $kind $(, $scale)?);)+
+                )+
+                unpacker.unpack_remaining();
+                Ok(values)
+            }
+
+            /// Packs the fields selected by `mask`.
+            pub(crate) fn pack_into(
+                &self,
+                packer: &mut Packer,
+                mask: $mask,
+            ) -> Result<(), EncodeError> {
+                $(
+                    if mask.contains($mask::$flag) {
+                        $(telemetry!(@pack packer, self.$field, $kind $(, $scale)?);)+
+                    }
+                )+
+                Ok(())
+            }
+        }
+    };
+
+    (@type f16) => { f32 };
+    (@type f32) => { f32 };
+    (@type i32) => { i32 };
+    (@type u8) => { u8 };
+
+    (@unpack $unpacker:ident, f16, $scale:literal) => { $unpacker.unpack_f16($scale)? };
+    (@unpack $unpacker:ident, f32, $scale:literal) => { $unpacker.unpack_f32($scale)? };
+    (@unpack $unpacker:ident, i32) => { $unpacker.unpack_i32()? };
+    (@unpack $unpacker:ident, u8) => { $unpacker.unpack_u8()? };
+
+    (@pack $packer:ident, $value:expr, f16, $scale:literal) => { $packer.pack_f16($value, $scale)? };
+    (@pack $packer:ident, $value:expr, f32, $scale:literal) => { $packer.pack_f32($value, $scale)? };
+    (@pack $packer:ident, $value:expr, i32) => { $packer.pack_i32($value)? };
+    (@pack $packer:ident, $value:expr, u8) => { $packer.pack_u8($value)? };
+}
+
+telemetry! {
+    /// Telemetry data returned by the motor controller.
+    ///
+    /// Contains temperatures, currents, voltages, rpm, and so on. Returned by
+    /// [`Command::GetValues`] or [`Command::GetValuesSelective`].
+    ///
+    /// With [`Command::GetValuesSelective`], only the fields specified by the
+    /// [`ValuesMask`] are populated; all others remain at their default.
+    ///
+    /// [`Command::GetValues`]: crate::Command::GetValues
+    /// [`Command::GetValuesSelective`]: crate::Command::GetValuesSelective
+    pub struct Values;
+
+    /// A bitmask used with [`Command::GetValuesSelective`] to request specific
+    /// telemetry fields. This allows for efficient communication by requesting only
+    /// the data you need, reducing bandwidth and processing overhead. Each flag
+    /// corresponds to a field in the [`Values`] struct.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vesc::ValuesMask;
+    ///
+    /// let mask = ValuesMask::RPM | ValuesMask::WATT_HOURS | ValuesMask::CONTROLLER_ID;
+    /// ```
+    ///
+    /// [`Command::GetValuesSelective`]: crate::Command::GetValuesSelective
+    pub struct ValuesMask;
+
+    TEMP_MOSFET = 0 { temp_mosfet: f16 / 10.0 }
+    TEMP_MOTOR = 1 { temp_motor: f16 / 10.0 }
+    AVG_CURRENT_MOTOR = 2 { avg_current_motor: f32 / 100.0 }
+    AVG_CURRENT_INPUT = 3 { avg_current_input: f32 / 100.0 }
+    AVG_CURRENT_D = 4 { avg_current_d: f32 / 100.0 }
+    AVG_CURRENT_Q = 5 { avg_current_q: f32 / 100.0 }
+    DUTY_CYCLE = 6 { duty_cycle: f16 / 1000.0 }
+    RPM = 7 { rpm: f32 / 1.0 }
+    VOLTAGE_IN = 8 { voltage_in: f16 / 10.0 }
+    AMP_HOURS = 9 { amp_hours: f32 / 10000.0 }
+    AMP_HOURS_CHARGED = 10 { amp_hours_charged: f32 / 10000.0 }
+    WATT_HOURS = 11 { watt_hours: f32 / 10000.0 }
+    WATT_HOURS_CHARGED = 12 { watt_hours_charged: f32 / 10000.0 }
+    TACHOMETER = 13 { tachometer: i32 }
+    TACHOMETER_ABS = 14 { tachometer_abs: i32 }
+    FAULT_CODE = 15 { fault_code: u8 }
+    PID_POS = 16 { pid_pos: f32 / 1000000.0 }
+    CONTROLLER_ID = 17 { controller_id: u8 }
+    TEMP_MOSFET_ALL = 18 {
+        temp_mosfet1: f16 / 10.0,
+        temp_mosfet2: f16 / 10.0,
+        temp_mosfet3: f16 / 10.0,
+    }
+    AVG_VOLTAGE_D = 19 { avg_voltage_d: f32 / 1000.0 }
+    AVG_VOLTAGE_Q = 20 { avg_voltage_q: f32 / 1000.0 }
+    STATUS = 21 { status: u8 }
+}
+
+impl Values {
+    /// Encodes the values as the reply to [`Command::GetValues`], the way a
+    /// controller sends them. Returns the number of bytes written.
+    ///
+    /// This is meant for devices and simulators that answer telemetry
+    /// requests.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vesc::{CommandReply, Values};
+    ///
+    /// let values = Values {
+    ///     rpm: 1500.0,
+    ///     ..Values::default()
+    /// };
+    /// let mut buf = [0u8; 128];
+    /// let len = values.encode(&mut buf).unwrap();
+    ///
+    /// let (_, reply) = vesc::decode(&buf[..len]).unwrap();
+    /// assert!(matches!(reply, CommandReply::GetValues(values) if values.rpm == 1500.0));
+    /// ```
+    ///
+    /// [`Command::GetValues`]: crate::Command::GetValues
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        write_with(buf, |packer| {
+            packer.pack_u8(CommandId::GetValues as u8)?;
+            self.pack_into(packer, ValuesMask::all())
+        })
+    }
+
+    /// Encodes the fields selected by `mask` as the reply to
+    /// [`Command::GetValuesSelective`]. Returns the number of bytes written.
+    ///
+    /// [`Command::GetValuesSelective`]: crate::Command::GetValuesSelective
+    pub fn encode_selective(&self, mask: ValuesMask, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        write_with(buf, |packer| {
+            packer.pack_u8(CommandId::GetValuesSelective as u8)?;
+            packer.pack_u32(mask.bits())?;
+            self.pack_into(packer, mask)
+        })
+    }
+}
@@ -0,0 +1,577 @@
+use googletest::prelude::*;
+
+use vesc::can::{
+    self, CanFrame, CanStatusTable, Command, HwType, LivenessTracker, Message, Reassembler,
+    Segmenter, Status, Status2, Status3, Status4, Status5, Status6,
+};
+use vesc::{CommandReply, DecodeError, EncodeError};
+
+#[test]
+fn encode_commands() {
+    let cases = [
+        (Command::SetDuty(0.25), 0x0005, [0, 0, 97, 168]),
+        (Command::SetCurrent(-2.5), 0x0105, [255, 255, 246, 60]),
+        (Command::SetCurrentBrake(10.0), 0x0205, [0, 0, 39, 16]),
+        (Command::SetRpm(1500), 0x0305, [0, 0, 5, 220]),
+        (Command::SetPos(90.0), 0x0405, [5, 93, 74, 128]),
+        (Command::SetCurrentRel(0.5), 0x0a05, [0, 0, 195, 80]),
+    ];
+    for (command, id, data) in cases {
+        let frame = can::encode(5, command).unwrap();
+        assert_that!(frame.id(), eq(id));
+        assert_that!(frame.data(), eq(data.as_slice()));
+    }
+}
+
+#[test]
+fn encode_value_out_of_range() {
+    for duty in [f32::NAN, f32::INFINITY, 30_000.0] {
+        let result = can::encode(5, Command::SetDuty(duty));
+        assert_that!(result, err(eq(&EncodeError::ValueOutOfRange)));
+    }
+}
+
+#[test]
+fn can_frame_new() {
+    let frame = CanFrame::new(0x1fff_ffff, &[1, 2, 3]).unwrap();
+    assert_that!(frame.id(), eq(0x1fff_ffff));
+    assert_that!(frame.data(), eq([1, 2, 3].as_slice()));
+
+    assert_that!(CanFrame::new(0x2000_0000, &[]), none());
+    assert_that!(CanFrame::new(0, &[0; 9]), none());
+}
+
+#[test]
+fn decode_status() {
+    let input = [255, 255, 250, 36, 0, 15, 254, 12];
+
+    let expected = (
+        eq(&42),
+        pat!(&Message::Status(pat!(Status {
+            rpm: approx_eq(-1500.0),
+            current: approx_eq(1.5),
+            duty_cycle: approx_eq(-0.5),
+        }))),
+    );
+    assert_that!(can::decode(0x092a, &input), ok(expected));
+}
+
+#[test]
+fn decode_status_2() {
+    let input = [0, 0, 48, 57, 0, 0, 19, 136];
+
+    let expected = (
+        eq(&7),
+        pat!(&Message::Status2(pat!(Status2 {
+            amp_hours: approx_eq(1.2345),
+            amp_hours_charged: approx_eq(0.5),
+        }))),
+    );
+    assert_that!(can::decode(0x0e07, &input), ok(expected));
+}
+
+#[test]
+fn decode_status_3() {
+    let input = [0, 1, 212, 192, 0, 0, 0, 0];
+
+    let expected = (
+        eq(&7),
+        pat!(&Message::Status3(pat!(Status3 {
+            watt_hours: approx_eq(12.0),
+            watt_hours_charged: approx_eq(0.0),
+        }))),
+    );
+    assert_that!(can::decode(0x0f07, &input), ok(expected));
+}
+
+#[test]
+fn decode_status_4() {
+    let input = [1, 99, 1, 144, 255, 244, 35, 40];
+
+    let expected = (
+        eq(&7),
+        pat!(&Message::Status4(pat!(Status4 {
+            temp_mosfet: approx_eq(35.5),
+            temp_motor: approx_eq(40.0),
+            current_input: approx_eq(-1.2),
+            pid_pos: approx_eq(180.0),
+        }))),
+    );
+    assert_that!(can::decode(0x1007, &input), ok(expected));
+}
+
+#[test]
+fn decode_status_5() {
+    let input = [0, 1, 226, 64, 1, 227, 0, 0];
+
+    let expected = (
+        eq(&7),
+        pat!(&Message::Status5(pat!(Status5 {
+            tachometer: eq(123456),
+            voltage_in: approx_eq(48.3),
+        }))),
+    );
+    assert_that!(can::decode(0x1b07, &input), ok(expected));
+}
+
+#[test]
+fn decode_status_6() {
+    let input = [5, 220, 0, 0, 12, 228, 255, 6];
+
+    let expected = (
+        eq(&7),
+        pat!(&Message::Status6(pat!(Status6 {
+            adc1: approx_eq(1.5),
+            adc2: approx_eq(0.0),
+            adc3: approx_eq(3.3),
+            ppm: approx_eq(-0.25),
+        }))),
+    );
+    assert_that!(can::decode(0x3a07, &input), ok(expected));
+}
+
+#[test]
+fn decode_invalid_length() {
+    let result = can::decode(0x092a, &[0, 0, 5, 220, 0, 15, 1]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+
+    let result = can::decode(0x092a, &[0, 0, 5, 220, 0, 15, 1, 244, 0]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+}
+
+#[test]
+fn decode_unknown_packet() {
+    let result = can::decode(0x032a, &[0, 0, 5, 220]);
+    assert_that!(result, err(eq(&DecodeError::UnknownPacket { id: 3 })));
+
+    let result = can::decode(0xff2a, &[]);
+    assert_that!(result, err(eq(&DecodeError::UnknownPacket { id: 255 })));
+}
+
+#[test]
+fn decode_invalid_id() {
+    let result = can::decode(0x1_092a, &[0, 0, 5, 220, 0, 15, 1, 244]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+}
+
+#[test]
+fn segment_short_command() {
+    let mut buf = [0u8; 16];
+
+    let segmenter = Segmenter::new(vesc::Command::GetValues, 1, 5, &mut buf).unwrap();
+    let frames: Vec<_> = segmenter
+        .map(|frame| (frame.id(), frame.data().to_vec()))
+        .collect();
+    assert_that!(frames, elements_are![eq(&(0x0805, vec![1, 0, 4]))]);
+}
+
+#[test]
+fn segment_long_command() {
+    let mut buf = [0u8; 16];
+
+    let data = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+    let command = vesc::Command::CustomAppData(&data);
+    let segmenter = Segmenter::new(command, 1, 5, &mut buf).unwrap();
+    let frames: Vec<_> = segmenter
+        .map(|frame| (frame.id(), frame.data().to_vec()))
+        .collect();
+    assert_that!(
+        frames,
+        elements_are![
+            eq(&(0x0505, vec![0, 36, 1, 2, 3, 4, 5, 6])),
+            eq(&(0x0505, vec![7, 7, 8, 9])),
+            eq(&(0x0705, vec![1, 0, 0, 10, 183, 64])),
+        ]
+    );
+}
+
+#[test]
+fn segment_buffer_too_small() {
+    let mut buf = [0u8; 4];
+
+    let command = vesc::Command::CustomAppData(&[0; 4]);
+    let result = Segmenter::new(command, 1, 5, &mut buf);
+    assert_that!(result.err(), some(eq(&EncodeError::BufferTooSmall)));
+}
+
+#[test]
+fn segment_and_reassemble() {
+    let mut buf = [0u8; 512];
+    let mut reassembler = Reassembler::<512>::new(5);
+
+    let data: Vec<u8> = (0..400).map(|i| i as u8).collect();
+    let command = vesc::Command::CustomAppData(&data);
+    let frames: Vec<_> = Segmenter::new(command, 1, 5, &mut buf).unwrap().collect();
+
+    // 37 frames fill the first 259 bytes, 24 long frames the remaining 142.
+    assert_that!(frames.len(), eq(62));
+    assert_that!(frames[37].id(), eq(0x0605));
+    assert_that!(frames[37].data()[..2], eq([1, 3]));
+
+    let (last, fill) = frames.split_last().unwrap();
+    for frame in fill {
+        let result = reassembler.feed(frame.id(), frame.data());
+        assert_that!(result, ok(none()));
+    }
+    let result = reassembler.feed(last.id(), last.data());
+    assert_that!(
+        result,
+        ok(some((
+            eq(&1),
+            pat!(&CommandReply::CustomAppData(eq(data.as_slice())))
+        )))
+    );
+}
+
+#[test]
+fn reassemble_short_reply() {
+    let mut reassembler = Reassembler::<512>::new(1);
+
+    let result = reassembler.feed(0x0801, &[42, 1, 36, 7, 208]);
+    assert_that!(
+        result,
+        ok(some((
+            eq(&42),
+            pat!(&CommandReply::CustomAppData(eq([7, 208].as_slice())))
+        )))
+    );
+}
+
+#[test]
+fn reassemble_ignores_other_frames() {
+    let mut reassembler = Reassembler::<512>::new(1);
+
+    // Addressed to another node.
+    assert_that!(reassembler.feed(0x0802, &[42, 1, 36]), ok(none()));
+    // A status broadcast.
+    assert_that!(
+        reassembler.feed(0x0901, &[0, 0, 5, 220, 0, 15, 1, 244]),
+        ok(none())
+    );
+}
+
+#[test]
+fn reassemble_checksum_mismatch() {
+    let mut reassembler = Reassembler::<512>::new(1);
+
+    assert_that!(
+        reassembler.feed(0x0501, &[0, 50, 0, 0, 1, 128, 0, 0]),
+        ok(none())
+    );
+    // The second fill frame is lost.
+    let result = reassembler.feed(0x0701, &[42, 1, 0, 9, 114, 238]);
+    assert_that!(
+        result,
+        err(pat!(DecodeError::ChecksumMismatch {
+            expected: eq(&0x72ee),
+            actual: anything(),
+        }))
+    );
+}
+
+#[test]
+fn reassemble_invalid_frames() {
+    let mut reassembler = Reassembler::<16>::new(1);
+
+    // Beyond the buffer.
+    let result = reassembler.feed(0x0601, &[0, 16, 1]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+    // Longer than the buffer.
+    let result = reassembler.feed(0x0701, &[42, 1, 0, 17, 0, 0]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+    // Truncated header.
+    let result = reassembler.feed(0x0701, &[42, 1, 0]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+}
+
+#[cfg(feature = "embedded-can")]
+mod embedded {
+    use embedded_can::{ExtendedId, Id, StandardId};
+    use googletest::prelude::*;
+
+    use vesc::can::{self, Command, Message, Reassembler, Segmenter, Status};
+    use vesc::{CommandReply, DecodeError};
+
+    #[derive(Debug, PartialEq)]
+    struct Frame {
+        id: Id,
+        remote: bool,
+        data: Vec<u8>,
+    }
+
+    impl embedded_can::Frame for Frame {
+        fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+            (data.len() <= 8).then(|| Self {
+                id: id.into(),
+                remote: false,
+                data: data.to_vec(),
+            })
+        }
+
+        fn new_remote(id: impl Into<Id>, dlc: usize) -> Option<Self> {
+            (dlc <= 8).then(|| Self {
+                id: id.into(),
+                remote: true,
+                data: vec![0; dlc],
+            })
+        }
+
+        fn is_extended(&self) -> bool {
+            matches!(self.id, Id::Extended(_))
+        }
+
+        fn is_remote_frame(&self) -> bool {
+            self.remote
+        }
+
+        fn id(&self) -> Id {
+            self.id
+        }
+
+        fn dlc(&self) -> usize {
+            self.data.len()
+        }
+
+        fn data(&self) -> &[u8] {
+            &self.data
+        }
+    }
+
+    fn extended(id: u32, data: &[u8]) -> Frame {
+        embedded_can::Frame::new(ExtendedId::new(id).unwrap(), data).unwrap()
+    }
+
+    #[test]
+    fn encode_frame() {
+        let frame: Frame = can::encode_frame(5, Command::SetRpm(1500)).unwrap();
+        assert_that!(frame, eq(&extended(0x0305, &[0, 0, 5, 220])));
+    }
+
+    #[test]
+    fn segmenter_frames() {
+        let mut buf = [0u8; 16];
+
+        let segmenter = Segmenter::new(vesc::Command::GetValues, 1, 5, &mut buf).unwrap();
+        let frames: Vec<Frame> = segmenter.frames().collect();
+        assert_that!(frames, elements_are![eq(&extended(0x0805, &[1, 0, 4]))]);
+    }
+
+    #[test]
+    fn decode_frame() {
+        let frame = extended(0x092a, &[0, 0, 5, 220, 0, 15, 1, 244]);
+
+        let expected = (
+            eq(&42),
+            pat!(&Message::Status(pat!(Status {
+                rpm: approx_eq(1500.0),
+                current: approx_eq(1.5),
+                duty_cycle: approx_eq(0.5),
+            }))),
+        );
+        assert_that!(can::decode_frame(&frame), ok(expected));
+    }
+
+    #[test]
+    fn decode_frame_rejects_non_vesc_frames() {
+        let id = StandardId::new(0x12a).unwrap();
+        let standard: Frame = embedded_can::Frame::new(id, &[0; 8]).unwrap();
+        let result = can::decode_frame(&standard);
+        assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+
+        let id = ExtendedId::new(0x092a).unwrap();
+        let remote: Frame = embedded_can::Frame::new_remote(id, 8).unwrap();
+        let result = can::decode_frame(&remote);
+        assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+    }
+
+    #[test]
+    fn reassembler_feed_frame() {
+        let mut reassembler = Reassembler::<512>::new(1);
+
+        let frame = extended(0x0801, &[42, 1, 36, 7, 208]);
+        assert_that!(
+            reassembler.feed_frame(&frame),
+            ok(some((
+                eq(&42),
+                pat!(&CommandReply::CustomAppData(eq([7, 208].as_slice())))
+            )))
+        );
+
+        let id = StandardId::new(0x0801 & 0x7ff).unwrap();
+        let standard: Frame = embedded_can::Frame::new(id, &[42, 1, 36]).unwrap();
+        assert_that!(reassembler.feed_frame(&standard), ok(none()));
+    }
+}
+
+#[test]
+fn status_table_keeps_latest_status() {
+    let mut table = CanStatusTable::<4>::new();
+
+    let status = [0, 0, 5, 220, 0, 15, 1, 244];
+    assert_that!(table.ingest(100, 0x092a, &status), ok(eq(&true)));
+    let status = [255, 255, 250, 36, 0, 15, 254, 12];
+    assert_that!(table.ingest(200, 0x092a, &status), ok(eq(&true)));
+    let status = [0, 1, 226, 64, 1, 227, 0, 0];
+    assert_that!(table.ingest(300, 0x1b2a, &status), ok(eq(&true)));
+
+    let entry = table.get(42).unwrap();
+    assert_that!(entry.last_seen, eq(300));
+    assert_that!(
+        entry.status,
+        some(pat!(Status {
+            rpm: approx_eq(-1500.0),
+            current: approx_eq(1.5),
+            duty_cycle: approx_eq(-0.5),
+        }))
+    );
+    assert_that!(
+        entry.status_5,
+        some(pat!(Status5 {
+            tachometer: eq(123456),
+            voltage_in: approx_eq(48.3),
+        }))
+    );
+    assert_that!(entry.status_4, none());
+    assert_that!(table.get(43), none());
+}
+
+#[test]
+fn status_table_aggregates() {
+    let mut table = CanStatusTable::<4>::new();
+    assert_that!(table.total_current_input(), approx_eq(0.0));
+    assert_that!(table.min_voltage_in(), none());
+    assert_that!(table.max_temperature(), none());
+
+    table
+        .ingest(0, 0x1001, &[1, 99, 1, 144, 0, 12, 0, 0])
+        .unwrap();
+    table
+        .ingest(0, 0x1002, &[1, 194, 1, 44, 0, 30, 0, 0])
+        .unwrap();
+    table
+        .ingest(0, 0x1b01, &[0, 0, 0, 0, 1, 227, 0, 0])
+        .unwrap();
+    table
+        .ingest(0, 0x1b02, &[0, 0, 0, 0, 1, 192, 0, 0])
+        .unwrap();
+
+    assert_that!(table.total_current_input(), approx_eq(4.2));
+    assert_that!(table.min_voltage_in(), some(approx_eq(44.8)));
+    assert_that!(table.max_temperature(), some(approx_eq(45.0)));
+}
+
+#[test]
+fn status_table_ignores_other_frames() {
+    let mut table = CanStatusTable::<4>::new();
+
+    assert_that!(table.ingest(0, 0x032a, &[0, 0, 5, 220]), ok(eq(&false)));
+    // A frame of another device on the bus, which is not a VESC identifier.
+    assert_that!(table.ingest(0, 0x18fe_f100, &[0; 8]), ok(eq(&false)));
+    assert_that!(
+        table.ingest(0, 0x092a, &[0, 0, 5]),
+        err(eq(&DecodeError::InvalidFrame))
+    );
+    assert_that!(table.iter().count(), eq(0));
+}
+
+#[test]
+fn status_table_full() {
+    let mut table = CanStatusTable::<2>::new();
+
+    let status = [0, 0, 5, 220, 0, 15, 1, 244];
+    assert_that!(table.ingest(0, 0x0901, &status), ok(eq(&true)));
+    assert_that!(table.ingest(0, 0x0902, &status), ok(eq(&true)));
+    assert_that!(table.ingest(0, 0x0903, &status), ok(eq(&false)));
+    assert_that!(table.ingest(1, 0x0902, &status), ok(eq(&true)));
+
+    let ids: Vec<_> = table.iter().map(|entry| entry.controller_id).collect();
+    assert_that!(ids, elements_are![eq(&1), eq(&2)]);
+}
+
+#[test]
+fn status_table_evicts_stale() {
+    let mut table = CanStatusTable::<2>::new();
+
+    let status = [0, 0, 5, 220, 0, 15, 1, 244];
+    table.ingest(100, 0x0901, &status).unwrap();
+    table.ingest(150, 0x0902, &status).unwrap();
+
+    table.evict_stale(200, 50);
+    let ids: Vec<_> = table.iter().map(|entry| entry.controller_id).collect();
+    assert_that!(ids, elements_are![eq(&2)]);
+
+    // The freed slot is reused.
+    assert_that!(table.ingest(200, 0x0903, &status), ok(eq(&true)));
+}
+
+#[test]
+fn encode_ping() {
+    let frame = can::encode(42, Command::Ping(1)).unwrap();
+    assert_that!(frame.id(), eq(0x112a));
+    assert_that!(frame.data(), eq([1].as_slice()));
+}
+
+#[test]
+fn decode_pong() {
+    let expected = (eq(&42), eq(&Message::Pong(HwType::Vesc)));
+    assert_that!(can::decode(0x1201, &[42, 0]), ok(expected));
+
+    let expected = (eq(&7), eq(&Message::Pong(HwType::Unknown(9))));
+    assert_that!(can::decode(0x1201, &[7, 9]), ok(expected));
+
+    // Old BMS firmware leaves out the hardware type.
+    let expected = (eq(&7), eq(&Message::Pong(HwType::VescBms)));
+    assert_that!(can::decode(0x1201, &[7]), ok(expected));
+
+    let result = can::decode(0x1201, &[]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+    let result = can::decode(0x1201, &[7, 0, 0]);
+    assert_that!(result, err(eq(&DecodeError::InvalidFrame)));
+}
+
+#[test]
+fn liveness_tracker_pings() {
+    let tracker = LivenessTracker::new([1, 2], 0, 10, 3);
+
+    let frames: Vec<_> = tracker.pings(9).collect();
+    assert_that!(
+        frames,
+        elements_are![
+            eq(&CanFrame::new(0x1101, &[9]).unwrap()),
+            eq(&CanFrame::new(0x1102, &[9]).unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn liveness_tracker_flags_silent_controllers() {
+    let mut tracker = LivenessTracker::new([1, 2, 3], 0, 10, 3);
+    assert_that!(tracker.any_lost(29), eq(false));
+
+    // A status broadcast and a pong count alike.
+    let status = [0, 0, 5, 220, 0, 15, 1, 244];
+    assert_that!(tracker.ingest(25, 0x0901, &status), ok(eq(&true)));
+    assert_that!(tracker.ingest(25, 0x1209, &[2, 0]), ok(eq(&true)));
+    // Neither from a tracked controller nor a message.
+    assert_that!(tracker.ingest(25, 0x0904, &status), ok(eq(&false)));
+    assert_that!(tracker.ingest(25, 0x0303, &[0, 0, 0, 0]), ok(eq(&false)));
+    // A frame of another device on the bus, which is not a VESC identifier.
+    assert_that!(tracker.ingest(28, 0x18fe_f101, &[0; 8]), ok(eq(&false)));
+
+    assert_that!(tracker.last_seen(1), some(eq(25)));
+    assert_that!(tracker.last_seen(3), some(eq(0)));
+    assert_that!(tracker.last_seen(4), none());
+
+    assert_that!(tracker.any_lost(30), eq(true));
+    assert_that!(tracker.lost(30).collect::<Vec<_>>(), elements_are![eq(&3)]);
+    assert_that!(
+        tracker.lost(55).collect::<Vec<_>>(),
+        elements_are![eq(&1), eq(&2), eq(&3)]
+    );
+
+    assert_that!(tracker.seen(60, 3), eq(true));
+    assert_that!(
+        tracker.lost(60).collect::<Vec<_>>(),
+        elements_are![eq(&1), eq(&2)]
+    );
+}
@@ -1,6 +1,6 @@
 use googletest::prelude::*;
 
-use vesc::{self, Command, EncodeError, ValuesMask};
+use vesc::{self, Command, EncodeCustom, EncodeError, ValuesMask};
 
 #[test]
 fn encode_get_values() {
@@ -70,6 +70,31 @@ fn encode_set_handbrake() {
     assert_that!(buf[..size], eq([2, 5, 10, 255, 255, 235, 176, 169, 253, 3]));
 }
 
+#[test]
+fn encode_rounds_to_nearest() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::SetCurrent(0.0019), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 5, 6, 0, 0, 0, 2, 237, 199, 3]));
+
+    let size = vesc::encode(Command::SetCurrent(-0.0019), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 5, 6, 255, 255, 255, 254, 68, 107, 3]));
+}
+
+#[test]
+fn encode_value_out_of_range() {
+    let mut buf = [0u8; 16];
+
+    for current in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 2.2e6, -2.2e6] {
+        let result = vesc::encode(Command::SetCurrent(current), &mut buf);
+        assert_that!(result, err(eq(&EncodeError::ValueOutOfRange)));
+    }
+
+    // A current just below the limit of the encoding still fits.
+    let result = vesc::encode(Command::SetCurrent(2_147_483.5), &mut buf);
+    assert_that!(result, ok(eq(&10)));
+}
+
 #[test]
 fn encode_forward_can() {
     let mut buf = [0u8; 16];
@@ -121,3 +146,480 @@ fn encode_buffer_too_small() {
         assert_that!(result, err(eq(&EncodeError::BufferTooSmall)));
     }
 }
+
+#[test]
+fn encode_payload_too_large() {
+    let mut buf = [0u8; 1024];
+    let command = Command::LispWriteCode {
+        offset: 0,
+        data: &[0; 508],
+    };
+    let result = vesc::encode(command, &mut buf);
+    assert_that!(result, err(eq(&EncodeError::PayloadTooLarge)));
+}
+
+#[test]
+fn encode_lisp_read_code() {
+    let mut buf = [0u8; 16];
+
+    let command = Command::LispReadCode {
+        len: 256,
+        offset: 0,
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([2, 9, 130, 0, 0, 1, 0, 0, 0, 0, 0, 60, 117, 3])
+    );
+}
+
+#[test]
+fn encode_lisp_write_code() {
+    let mut buf = [0u8; 32];
+
+    let command = Command::LispWriteCode {
+        offset: 512,
+        data: b"(+ 1 2)",
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([
+            2, 12, 131, 0, 0, 2, 0, 40, 43, 32, 49, 32, 50, 41, 96, 59, 3
+        ])
+    );
+}
+
+#[test]
+fn encode_lisp_write_code_long_frame() {
+    let mut buf = [0u8; 320];
+
+    let command = Command::LispWriteCode {
+        offset: 0,
+        data: &[0x55; 300],
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(size, eq(311));
+    assert_that!(buf[..8], eq([3, 1, 49, 131, 0, 0, 0, 0]));
+    assert_that!(buf[8..308], each(eq(&0x55)));
+    assert_that!(buf[308..size], eq([149, 138, 3]));
+}
+
+#[test]
+fn encode_lisp_erase_code() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::LispEraseCode(1024), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 5, 132, 0, 0, 4, 0, 103, 18, 3]));
+}
+
+#[test]
+fn encode_lisp_set_running() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::LispSetRunning(true), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 2, 133, 1, 244, 76, 3]));
+}
+
+#[test]
+fn encode_lisp_get_stats() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::LispGetStats, &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 1, 134, 241, 78, 3]));
+}
+
+#[test]
+fn encode_lisp_repl_cmd() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::LispReplCmd("(+ 1 2)"), &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([2, 9, 138, 40, 43, 32, 49, 32, 50, 41, 0, 163, 3, 3])
+    );
+}
+
+#[test]
+fn encode_lisp_stream_code() {
+    let mut buf = [0u8; 32];
+
+    let command = Command::LispStreamCode {
+        offset: 0,
+        total_len: 7,
+        restart: true,
+        data: b"(+ 1 2)",
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([
+            2, 17, 139, 0, 0, 0, 0, 0, 0, 0, 7, 1, 40, 43, 32, 49, 32, 50, 41, 185, 36, 3
+        ])
+    );
+}
+
+#[test]
+fn encode_file_list() {
+    let mut buf = [0u8; 32];
+
+    let command = Command::FileList {
+        path: "/logs",
+        from: None,
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([2, 7, 140, 47, 108, 111, 103, 115, 0, 85, 163, 3])
+    );
+
+    let command = Command::FileList {
+        path: "/logs",
+        from: Some("b.log"),
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([
+            2, 13, 140, 47, 108, 111, 103, 115, 0, 98, 46, 108, 111, 103, 0, 215, 96, 3
+        ])
+    );
+}
+
+#[test]
+fn encode_file_read() {
+    let mut buf = [0u8; 32];
+
+    let command = Command::FileRead {
+        path: "/a.log",
+        offset: 400,
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([
+            2, 12, 141, 47, 97, 46, 108, 111, 103, 0, 0, 0, 1, 144, 236, 21, 3
+        ])
+    );
+}
+
+#[test]
+fn encode_file_write() {
+    let mut buf = [0u8; 32];
+
+    let command = Command::FileWrite {
+        path: "/a",
+        offset: 0,
+        total_len: 3,
+        data: &[1, 2, 3],
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([
+            2, 15, 142, 47, 97, 0, 0, 0, 0, 0, 0, 0, 0, 3, 1, 2, 3, 107, 168, 3
+        ])
+    );
+}
+
+#[test]
+fn encode_file_mkdir() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::FileMkdir("/x"), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 4, 143, 47, 120, 0, 34, 209, 3]));
+}
+
+#[test]
+fn encode_file_remove() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::FileRemove("/x"), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 4, 144, 47, 120, 0, 237, 152, 3]));
+}
+
+#[test]
+fn encode_string_with_nul() {
+    let mut buf = [0u8; 32];
+
+    let commands = [
+        Command::FileMkdir("/logs\0/a"),
+        Command::FileList {
+            path: "/logs",
+            from: Some("a\0"),
+        },
+        Command::LispReplCmd("(+ 1\0 2)"),
+    ];
+
+    for command in commands {
+        let result = vesc::encode(command, &mut buf);
+        assert_that!(result, err(eq(&EncodeError::InvalidString)));
+    }
+}
+
+#[test]
+fn encode_get_custom_config_xml() {
+    let mut buf = [0u8; 16];
+
+    let command = Command::GetCustomConfigXml {
+        index: 0,
+        len: 400,
+        offset: 0,
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([2, 10, 92, 0, 0, 0, 1, 144, 0, 0, 0, 0, 98, 216, 3])
+    );
+}
+
+#[test]
+fn encode_get_custom_config() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::GetCustomConfig(1), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 2, 93, 1, 104, 194, 3]));
+}
+
+#[test]
+fn encode_get_custom_config_default() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::GetCustomConfigDefault(1), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 2, 94, 1, 61, 145, 3]));
+}
+
+#[test]
+fn encode_set_custom_config() {
+    let mut buf = [0u8; 16];
+
+    let command = Command::SetCustomConfig {
+        index: 1,
+        data: &[1, 2, 3],
+    };
+    let size = vesc::encode(command, &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 5, 95, 1, 1, 2, 3, 103, 78, 3]));
+}
+
+#[test]
+fn encode_custom_app_data() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode(Command::CustomAppData(&[1, 2, 3]), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 4, 36, 1, 2, 3, 156, 142, 3]));
+}
+
+#[test]
+fn encode_can_fwd_frame() {
+    let mut buf = [0u8; 32];
+
+    let cmd = Command::CanFwdFrame {
+        id: 0x123,
+        extended: false,
+        data: &[1, 2, 3],
+    };
+    let size = vesc::encode(cmd, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([2, 9, 85, 0, 0, 1, 35, 0, 1, 2, 3, 185, 28, 3])
+    );
+
+    let cmd = Command::CanFwdFrame {
+        id: 0x18ff50e5,
+        extended: true,
+        data: &[0, 1, 2, 3, 4, 5, 6, 7],
+    };
+    let size = vesc::encode(cmd, &mut buf).unwrap();
+    assert_that!(
+        buf[..size],
+        eq([
+            2, 14, 85, 24, 255, 80, 229, 1, 0, 1, 2, 3, 4, 5, 6, 7, 250, 187, 3
+        ])
+    );
+}
+
+#[test]
+fn encode_can_fwd_frame_invalid() {
+    let mut buf = [0u8; 32];
+
+    let cmds = [
+        Command::CanFwdFrame {
+            id: 0x800,
+            extended: false,
+            data: &[],
+        },
+        Command::CanFwdFrame {
+            id: 0x2000_0000,
+            extended: true,
+            data: &[],
+        },
+        Command::CanFwdFrame {
+            id: 0x100,
+            extended: false,
+            data: &[0; 9],
+        },
+    ];
+    for cmd in cmds {
+        let result = vesc::encode(cmd, &mut buf);
+        assert_that!(result, err(eq(&EncodeError::InvalidCanFrame)));
+    }
+}
+
+struct Speed(u16);
+
+impl EncodeCustom for Speed {
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let bytes = self.0.to_be_bytes();
+        buf.get_mut(..bytes.len())
+            .ok_or(EncodeError::BufferTooSmall)?
+            .copy_from_slice(&bytes);
+        Ok(bytes.len())
+    }
+}
+
+#[test]
+fn encode_custom_payload() {
+    let mut buf = [0u8; 16];
+
+    let size = vesc::encode_custom(&Speed(2000), &mut buf).unwrap();
+    assert_that!(buf[..size], eq([2, 3, 36, 7, 208, 8, 236, 3]));
+}
+
+#[test]
+fn encode_custom_payload_buffer_too_small() {
+    for n in 0..8 {
+        let mut buf = vec![0u8; n];
+        let result = vesc::encode_custom(&Speed(2000), &mut buf);
+        assert_that!(result, err(eq(&EncodeError::BufferTooSmall)));
+    }
+}
+
+#[test]
+fn encode_to_sink() {
+    let mut frame = vec![];
+
+    let size = vesc::encode_to(Command::GetValues, |bytes| frame.extend_from_slice(bytes)).unwrap();
+    assert_that!(size, eq(6));
+    assert_that!(frame, eq(&[2, 1, 4, 64, 132, 3]));
+}
+
+#[test]
+fn encode_to_sink_long_frame() {
+    let mut frame = vec![];
+
+    let command = Command::LispWriteCode {
+        offset: 0,
+        data: &[0x55; 300],
+    };
+    let size = vesc::encode_to(command, |bytes| frame.extend_from_slice(bytes)).unwrap();
+    assert_that!(size, eq(311));
+    assert_that!(frame[..8], eq([3, 1, 49, 131, 0, 0, 0, 0]));
+    assert_that!(frame[8..308], each(eq(&0x55)));
+    assert_that!(frame[308..], eq([149, 138, 3]));
+}
+
+#[test]
+fn encode_to_sink_matches_encode() {
+    let commands = [
+        Command::SetCurrent(1.0),
+        Command::SetRpm(-1500),
+        Command::GetValuesSelective(ValuesMask::RPM | ValuesMask::VOLTAGE_IN),
+        Command::LispWriteCode {
+            offset: 512,
+            data: &[0xaa; 507],
+        },
+    ];
+
+    for command in commands {
+        let mut buf = [0u8; 1024];
+        let size = vesc::encode(command, &mut buf).unwrap();
+
+        let mut frame = vec![];
+        let streamed = vesc::encode_to(command, |bytes| frame.extend_from_slice(bytes)).unwrap();
+        assert_that!(streamed, eq(size));
+        assert_that!(frame, eq(&buf[..size]));
+    }
+}
+
+#[test]
+fn encode_to_sink_payload_too_large() {
+    let mut frame = vec![];
+
+    let command = Command::LispWriteCode {
+        offset: 0,
+        data: &[0; 508],
+    };
+    let result = vesc::encode_to(command, |bytes| frame.extend_from_slice(bytes));
+    assert_that!(result, err(eq(&EncodeError::PayloadTooLarge)));
+    assert_that!(frame, is_empty());
+}
+
+#[test]
+fn encoded_len_matches_encode() {
+    let commands = [
+        Command::GetValues,
+        Command::SetRpm(-1500),
+        Command::ForwardCan(3, &Command::SetCurrent(1.0)),
+        Command::ForwardCan(3, &Command::ForwardCan(7, &Command::GetValues)),
+        Command::LispWriteCode {
+            offset: 0,
+            data: &[0x55; 252],
+        },
+        Command::LispWriteCode {
+            offset: 0,
+            data: &[0x55; 300],
+        },
+    ];
+
+    for command in commands {
+        let mut buf = [0u8; Command::MAX_FRAME_LEN];
+        let size = vesc::encode(command, &mut buf).unwrap();
+        assert_that!(command.encoded_len(), ok(eq(&size)));
+    }
+}
+
+#[test]
+fn encoded_len_long_frame_header() {
+    // 255 bytes of payload still fit a short frame, 256 need a long one.
+    let short = Command::LispWriteCode {
+        offset: 0,
+        data: &[0; 250],
+    };
+    let long = Command::LispWriteCode {
+        offset: 0,
+        data: &[0; 251],
+    };
+    assert_that!(short.encoded_len(), ok(eq(&260)));
+    assert_that!(long.encoded_len(), ok(eq(&262)));
+}
+
+#[test]
+fn encoded_len_max_frame_len() {
+    let command = Command::LispWriteCode {
+        offset: 0,
+        data: &[0; 507],
+    };
+    assert_that!(command.encoded_len(), ok(eq(&Command::MAX_FRAME_LEN)));
+}
+
+#[test]
+fn encoded_len_errors() {
+    let command = Command::LispWriteCode {
+        offset: 0,
+        data: &[0; 508],
+    };
+    assert_that!(
+        command.encoded_len(),
+        err(eq(&EncodeError::PayloadTooLarge))
+    );
+
+    let command = Command::CanFwdFrame {
+        id: 0x800,
+        extended: false,
+        data: &[],
+    };
+    assert_that!(
+        command.encoded_len(),
+        err(eq(&EncodeError::InvalidCanFrame))
+    );
+}
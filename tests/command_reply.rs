@@ -1,6 +1,6 @@
 use googletest::prelude::*;
 
-use vesc::{CommandReply, DecodeError, Values};
+use vesc::{CommandReply, DecodeError, FileEntry, LispBinding, Values, ValuesMask};
 
 #[test]
 fn decode_get_values_zero_rpm() {
@@ -124,6 +124,67 @@ fn decode_get_values_reverse_rpm() {
     assert_that!(vesc::decode(&input), ok(expected));
 }
 
+#[test]
+fn decode_get_values_older_firmware() {
+    // A reply that ends after the position, before the controller ID.
+    let input = [
+        2, 58, 4, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        119, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 1, 116, 0, 0, 0, 0, 255, 255, 131, 64, 0, 2, 168, 254,
+        0, 18, 6, 65, 224, 116, 219, 3,
+    ];
+
+    let expected = (
+        eq(&63),
+        pat!(&CommandReply::GetValues(pat!(Values {
+            temp_mosfet: approx_eq(27.6),
+            voltage_in: approx_eq(37.5),
+            tachometer_abs: eq(174334),
+            pid_pos: approx_eq(302.39996),
+            controller_id: eq(0),
+            temp_mosfet1: approx_eq(0.0),
+            avg_voltage_q: approx_eq(0.0),
+            status: eq(0),
+            ..
+        }))),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_get_values_newer_firmware() {
+    // A reply with five more bytes after the status.
+    let input = [
+        2, 79, 4, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        119, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 1, 116, 0, 0, 0, 0, 255, 255, 131, 64, 0, 2, 168, 254,
+        0, 18, 6, 65, 224, 20, 1, 21, 252, 216, 252, 202, 0, 0, 0, 8, 0, 0, 0, 12, 0, 0, 0, 0, 42,
+        1, 188, 129, 3,
+    ];
+
+    let expected = (
+        eq(&84),
+        pat!(&CommandReply::GetValues(pat!(Values {
+            controller_id: eq(20),
+            avg_voltage_q: approx_eq(0.012),
+            status: eq(0),
+            ..
+        }))),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_get_values_partial_field() {
+    // A reply that ends within the first MOSFET temperature.
+    let input = [
+        2, 60, 4, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        119, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 1, 116, 0, 0, 0, 0, 255, 255, 131, 64, 0, 2, 168, 254,
+        0, 18, 6, 65, 224, 20, 1, 97, 29, 3,
+    ];
+
+    let expected = &DecodeError::InvalidFrame;
+    assert_that!(vesc::decode(&input), err(eq(expected)));
+}
+
 #[test]
 fn decode_get_values_selective_zero_rpm() {
     let input = [
@@ -190,6 +251,338 @@ fn decode_get_values_selective_reverse_rpm() {
     assert_that!(vesc::decode(&input), ok(expected));
 }
 
+#[test]
+fn encode_get_values_round_trip() {
+    let input = [
+        2, 74, 4, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        119, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 1, 116, 0, 0, 0, 0, 255, 255, 131, 64, 0, 2, 168, 254,
+        0, 18, 6, 65, 224, 20, 1, 21, 252, 216, 252, 202, 0, 0, 0, 8, 0, 0, 0, 12, 0, 218, 113, 3,
+    ];
+    let Ok((_, CommandReply::GetValues(values))) = vesc::decode(&input) else {
+        panic!("not a values reply");
+    };
+
+    let mut buf = [0u8; 128];
+    let len = values.encode(&mut buf).unwrap();
+    assert_that!(len, eq(input.len()));
+
+    // The position is scaled beyond the precision of an `f32`, so it is
+    // compared after decoding rather than byte for byte.
+    let Ok((_, CommandReply::GetValues(decoded))) = vesc::decode(&buf[..len]) else {
+        panic!("not a values reply");
+    };
+    assert_that!(decoded, eq(values));
+}
+
+#[test]
+fn encode_get_values_selective_round_trip() {
+    let input = [
+        2, 23, 50, 0, 2, 161, 138, 0, 0, 0, 0, 0, 10, 255, 255, 246, 213, 1, 118, 255, 255, 181,
+        218, 0, 20, 94, 130, 3,
+    ];
+    let Ok((_, CommandReply::GetValuesSelective(values))) = vesc::decode(&input) else {
+        panic!("not a values reply");
+    };
+
+    let mask = ValuesMask::TEMP_MOTOR
+        | ValuesMask::AVG_CURRENT_INPUT
+        | ValuesMask::RPM
+        | ValuesMask::VOLTAGE_IN
+        | ValuesMask::TACHOMETER
+        | ValuesMask::FAULT_CODE
+        | ValuesMask::CONTROLLER_ID;
+    let mut buf = [0u8; 128];
+    let len = values.encode_selective(mask, &mut buf).unwrap();
+    assert_that!(buf[..len], eq(&input));
+}
+
+#[test]
+fn decode_lisp_read_code() {
+    let input = [
+        2, 16, 130, 0, 0, 0, 7, 0, 0, 0, 0, 40, 43, 32, 49, 32, 50, 41, 73, 49, 3,
+    ];
+
+    let expected = (
+        eq(&21),
+        pat!(&CommandReply::LispReadCode {
+            total_len: eq(7),
+            offset: eq(0),
+            data: eq(b"(+ 1 2)".as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_lisp_read_code_long_frame() {
+    let mut input = vec![3, 1, 53, 130, 0, 0, 1, 44, 0, 0, 0, 0];
+    input.extend_from_slice(&[0xaa; 300]);
+    input.extend_from_slice(&[115, 213, 3]);
+
+    let expected = (
+        eq(&315),
+        pat!(&CommandReply::LispReadCode {
+            total_len: eq(300),
+            offset: eq(0),
+            data: each(eq(&0xaa)),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_lisp_write_code() {
+    let input = [2, 6, 131, 1, 0, 0, 2, 0, 214, 243, 3];
+
+    let expected = (
+        eq(&11),
+        pat!(&CommandReply::LispWriteCode {
+            ok: eq(true),
+            offset: eq(512),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_lisp_erase_code() {
+    let input = [2, 2, 132, 1, 199, 125, 3];
+
+    let expected = (eq(&7), pat!(&CommandReply::LispEraseCode(eq(true))));
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_lisp_set_running() {
+    let input = [2, 2, 133, 0, 228, 109, 3];
+
+    let expected = (eq(&7), pat!(&CommandReply::LispSetRunning(eq(false))));
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_lisp_get_stats() {
+    let input = [
+        2, 27, 134, 4, 210, 22, 46, 255, 255, 115, 112, 101, 101, 100, 0, 64, 32, 0, 0, 99, 111,
+        117, 110, 116, 0, 194, 40, 0, 0, 205, 39, 3,
+    ];
+
+    let (size, reply) = vesc::decode(&input).unwrap();
+    assert_that!(size, eq(32));
+
+    let CommandReply::LispGetStats(stats) = reply else {
+        panic!("unexpected reply: {reply:?}");
+    };
+    assert_that!(stats.cpu_use, approx_eq(12.34));
+    assert_that!(stats.heap_use, approx_eq(56.78));
+    assert_that!(stats.mem_use, approx_eq(-0.01));
+    assert_that!(
+        stats.bindings().collect::<Vec<_>>(),
+        elements_are![
+            eq(&LispBinding {
+                name: "speed",
+                value: 2.5
+            }),
+            eq(&LispBinding {
+                name: "count",
+                value: -42.0
+            }),
+        ]
+    );
+}
+
+#[test]
+fn decode_lisp_print() {
+    let input = [2, 4, 135, 62, 32, 51, 82, 103, 3];
+
+    let expected = (eq(&9), pat!(&CommandReply::LispPrint(eq("> 3"))));
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_lisp_stream_code() {
+    let input = [2, 7, 139, 0, 0, 0, 0, 255, 253, 221, 155, 3];
+
+    let expected = (
+        eq(&12),
+        pat!(&CommandReply::LispStreamCode {
+            offset: eq(0),
+            result: eq(-3),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_file_list() {
+    let input = [
+        2, 20, 140, 1, 97, 46, 108, 111, 103, 0, 0, 0, 4, 210, 115, 117, 98, 0, 255, 255, 255, 255,
+        157, 131, 3,
+    ];
+
+    let (size, reply) = vesc::decode(&input).unwrap();
+    assert_that!(size, eq(25));
+
+    let CommandReply::FileList(list) = reply else {
+        panic!("unexpected reply: {reply:?}");
+    };
+    assert_that!(list.has_more, eq(true));
+    assert_that!(list.next_from(), some(eq("sub")));
+    assert_that!(
+        list.entries().collect::<Vec<_>>(),
+        elements_are![
+            eq(&FileEntry {
+                name: "a.log",
+                is_dir: false,
+                size: 1234,
+            }),
+            eq(&FileEntry {
+                name: "sub",
+                is_dir: true,
+                size: 0,
+            }),
+        ]
+    );
+}
+
+#[test]
+fn decode_file_read() {
+    let input = [2, 12, 141, 0, 0, 1, 144, 0, 0, 1, 147, 1, 2, 3, 2, 157, 3];
+
+    let expected = (
+        eq(&17),
+        pat!(&CommandReply::FileRead {
+            offset: eq(400),
+            total_len: eq(403),
+            data: eq([1, 2, 3].as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_file_write() {
+    let input = [2, 6, 142, 1, 0, 0, 1, 144, 78, 90, 3];
+
+    let expected = (
+        eq(&11),
+        pat!(&CommandReply::FileWrite {
+            ok: eq(true),
+            offset: eq(400),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_file_mkdir() {
+    let input = [2, 2, 143, 1, 27, 135, 3];
+
+    let expected = (eq(&7), pat!(&CommandReply::FileMkdir(eq(true))));
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_file_remove() {
+    let input = [2, 2, 144, 0, 24, 235, 3];
+
+    let expected = (eq(&7), pat!(&CommandReply::FileRemove(eq(false))));
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_get_custom_config_xml() {
+    let input = [2, 13, 92, 0, 0, 0, 0, 10, 0, 0, 0, 4, 1, 2, 3, 120, 47, 3];
+
+    let expected = (
+        eq(&18),
+        pat!(&CommandReply::GetCustomConfigXml {
+            index: eq(0),
+            total_len: eq(10),
+            offset: eq(4),
+            data: eq([1, 2, 3].as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_get_custom_config() {
+    let input = [
+        2, 20, 93, 1, 222, 173, 190, 239, 0, 0, 48, 212, 1, 2, 255, 231, 62, 128, 0, 0, 3, 64, 109,
+        184, 3,
+    ];
+
+    let expected = (
+        eq(&25),
+        pat!(&CommandReply::GetCustomConfig {
+            index: eq(1),
+            data: eq([
+                222, 173, 190, 239, 0, 0, 48, 212, 1, 2, 255, 231, 62, 128, 0, 0, 3, 64
+            ]
+            .as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_get_custom_config_default() {
+    let input = [2, 5, 94, 1, 1, 2, 3, 205, 31, 3];
+
+    let expected = (
+        eq(&10),
+        pat!(&CommandReply::GetCustomConfigDefault {
+            index: eq(1),
+            data: eq([1, 2, 3].as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_set_custom_config() {
+    let input = [2, 1, 95, 171, 26, 3];
+
+    let expected = (eq(&6), pat!(&CommandReply::SetCustomConfig));
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_custom_app_data() {
+    let input = [2, 4, 36, 1, 2, 3, 156, 142, 3];
+
+    let expected = (
+        eq(&9),
+        pat!(&CommandReply::CustomAppData(eq([1, 2, 3].as_slice()))),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_can_fwd_frame() {
+    let input = [2, 8, 85, 24, 255, 80, 229, 1, 170, 187, 141, 27, 3];
+
+    let expected = (
+        eq(&13),
+        pat!(&CommandReply::CanFwdFrame {
+            id: eq(0x18ff50e5),
+            extended: eq(true),
+            data: eq([170, 187].as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode(&input), ok(expected));
+}
+
+#[test]
+fn decode_can_fwd_frame_too_long() {
+    let input = [
+        2, 15, 85, 0, 0, 1, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 36, 20, 3,
+    ];
+
+    assert_that!(vesc::decode(&input), err(eq(&DecodeError::InvalidFrame)));
+}
+
 #[test]
 fn decode_incomplete_data() {
     let input = [
@@ -250,7 +643,7 @@ fn decode_wrong_payload_len_gt_payload() {
         2, 23, 50, 0, 2, 161, 138, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 128, 255, 255, 158, 70, 0, 1,
         63, 148, 3,
     ];
-    input[1] = input[1] + 1;
+    input[1] += 1;
 
     let expected = &DecodeError::InvalidFrame;
     assert_that!(vesc::decode(&input), err(eq(expected)));
@@ -262,7 +655,7 @@ fn decode_wrong_payload_len_lt_payload() {
         2, 23, 50, 0, 2, 161, 138, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 128, 255, 255, 158, 70, 0, 1,
         63, 148, 3,
     ];
-    input[1] = input[1] - 1;
+    input[1] -= 1;
 
     let expected = &DecodeError::InvalidFrame;
     assert_that!(vesc::decode(&input), err(eq(expected)));
@@ -278,3 +671,61 @@ fn decode_invalid_frame_end_witch_checksum_mismatch() {
     let expected = &DecodeError::InvalidFrame;
     assert_that!(vesc::decode(&input), err(eq(expected)));
 }
+
+#[test]
+fn decode_raw_unknown_packet() {
+    let input = [2, 2, 200, 1, 143, 220, 3];
+
+    let expected = (
+        eq(&7),
+        pat!(&CommandReply::Raw {
+            id: eq(200),
+            payload: eq([1].as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode_raw(&input), ok(expected));
+}
+
+#[test]
+fn decode_raw_unknown_long_packet() {
+    let input = [[3, 1, 45, 201].as_slice(), &[0xaa; 300], &[62, 127, 3]].concat();
+
+    let expected = (
+        eq(&307),
+        pat!(&CommandReply::Raw {
+            id: eq(201),
+            payload: eq([0xaa; 300].as_slice()),
+        }),
+    );
+    assert_that!(vesc::decode_raw(&input), ok(expected));
+}
+
+#[test]
+fn decode_raw_known_packet() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    let expected = (
+        eq(&9),
+        pat!(&CommandReply::CustomAppData(eq([7, 8, 9].as_slice()))),
+    );
+    assert_that!(vesc::decode_raw(&input), ok(expected));
+}
+
+#[test]
+fn decode_raw_unknown_packet_with_checksum_mismatch() {
+    let input = [2, 2, 200, 1, 143, 221, 3];
+
+    let expected = &DecodeError::ChecksumMismatch {
+        expected: 0x8fdd,
+        actual: 0x8fdc,
+    };
+    assert_that!(vesc::decode_raw(&input), err(eq(expected)));
+}
+
+#[test]
+fn decode_raw_incomplete_unknown_packet() {
+    let input = [2, 2, 200, 1, 143];
+
+    let expected = &DecodeError::IncompleteData;
+    assert_that!(vesc::decode_raw(&input), err(eq(expected)));
+}
@@ -0,0 +1,199 @@
+use googletest::prelude::*;
+
+use vesc::{
+    Command, CommandReply, ConfigParamKind, ConfigValue, CustomConfigError,
+    CustomConfigXmlDownload, EncodeError,
+};
+
+fn xml_chunk(total_len: u32, offset: u32, data: &[u8]) -> CommandReply<'_> {
+    CommandReply::GetCustomConfigXml {
+        index: 0,
+        total_len,
+        offset,
+        data,
+    }
+}
+
+#[test]
+fn xml_download_assembles_chunks() {
+    let mut buf = [0u8; 16];
+    let mut download = CustomConfigXmlDownload::new(0, &mut buf).with_chunk_len(4);
+
+    assert_that!(
+        download.next_command(),
+        some(pat!(Command::GetCustomConfigXml {
+            index: eq(0),
+            len: eq(4),
+            offset: eq(0),
+        }))
+    );
+    assert_that!(
+        download.handle_reply(&xml_chunk(6, 0, &[1, 2, 3, 4])),
+        ok(eq(&true))
+    );
+    assert_that!(download.data(), none());
+
+    assert_that!(
+        download.next_command(),
+        some(pat!(Command::GetCustomConfigXml {
+            index: eq(0),
+            len: eq(2),
+            offset: eq(4),
+        }))
+    );
+    assert_that!(
+        download.handle_reply(&xml_chunk(6, 4, &[5, 6])),
+        ok(eq(&true))
+    );
+
+    assert_that!(download.next_command(), none());
+    assert_that!(download.data(), some(eq([1, 2, 3, 4, 5, 6].as_slice())));
+}
+
+#[test]
+fn xml_download_ignores_unrelated_replies() {
+    let mut buf = [0u8; 16];
+    let mut download = CustomConfigXmlDownload::new(0, &mut buf).with_chunk_len(4);
+
+    assert_that!(
+        download.handle_reply(&xml_chunk(6, 0, &[1, 2, 3, 4])),
+        ok(eq(&true))
+    );
+    assert_that!(
+        download.handle_reply(&xml_chunk(6, 0, &[1, 2, 3, 4])),
+        ok(eq(&false))
+    );
+    assert_that!(
+        download.handle_reply(&CommandReply::SetCustomConfig),
+        ok(eq(&false))
+    );
+    let other_index = CommandReply::GetCustomConfigXml {
+        index: 1,
+        total_len: 6,
+        offset: 4,
+        data: &[5, 6],
+    };
+    assert_that!(download.handle_reply(&other_index), ok(eq(&false)));
+    assert_that!(download.data(), none());
+}
+
+#[test]
+fn xml_download_buffer_too_small() {
+    let mut buf = [0u8; 4];
+    let mut download = CustomConfigXmlDownload::new(0, &mut buf);
+
+    let result = download.handle_reply(&xml_chunk(6, 0, &[1, 2, 3, 4]));
+    assert_that!(result, err(eq(&CustomConfigError::BufferTooSmall)));
+}
+
+#[test]
+fn param_kind_write_out_of_range() {
+    let mut data = [0u8; 4];
+
+    let values = [
+        (ConfigParamKind::U8, ConfigValue::Int(256)),
+        (ConfigParamKind::U8, ConfigValue::Int(-1)),
+        (ConfigParamKind::I8, ConfigValue::Int(-129)),
+        (ConfigParamKind::U16, ConfigValue::Float(65536.0)),
+        (ConfigParamKind::I16, ConfigValue::Int(40000)),
+        (ConfigParamKind::U32, ConfigValue::Int(1 << 32)),
+        (ConfigParamKind::I32, ConfigValue::Float(f32::NAN)),
+    ];
+    for (kind, value) in values {
+        let result = kind.write(&mut data, 0, 1.0, value);
+        assert_that!(result, err(eq(&EncodeError::ValueOutOfRange)));
+    }
+    assert_that!(data, eq([0; 4]));
+
+    let result = ConfigParamKind::U8.write(&mut data, 0, 1.0, ConfigValue::Int(255));
+    assert_that!(result, ok(eq(&())));
+    assert_that!(data, eq([255, 0, 0, 0]));
+}
+
+#[cfg(feature = "std")]
+mod params {
+    use googletest::prelude::*;
+
+    use vesc::{ConfigParam, ConfigParamKind, ConfigValue, CustomConfigParams};
+
+    const XML: &str = include_str!("data/custom_config.xml");
+    const XML_COMPRESSED: &[u8] = include_bytes!("data/custom_config.xml.qz");
+    const SERIALIZED: [u8; 18] = [
+        222, 173, 190, 239, 0, 0, 48, 212, 1, 2, 255, 231, 62, 128, 0, 0, 3, 64,
+    ];
+
+    #[test]
+    fn params_from_compressed_xml() {
+        let params = CustomConfigParams::from_compressed_xml(XML_COMPRESSED).unwrap();
+        assert_that!(params, eq(&CustomConfigParams::from_xml(XML).unwrap()));
+    }
+
+    #[test]
+    fn params_layout() {
+        let params = CustomConfigParams::from_xml(XML).unwrap();
+
+        let param = |name: &str, kind, scale, offset| ConfigParam {
+            name: name.into(),
+            kind,
+            scale,
+            offset,
+        };
+        assert_that!(
+            params.params(),
+            elements_are![
+                eq(&param("kp", ConfigParamKind::F32, 1000.0, 4)),
+                eq(&param("enabled", ConfigParamKind::Bool, 1.0, 8)),
+                eq(&param("mode", ConfigParamKind::U8, 1.0, 9)),
+                eq(&param("tilt", ConfigParamKind::F16, 10.0, 10)),
+                eq(&param("ki", ConfigParamKind::F32Auto, 1.0, 12)),
+                eq(&param("hz", ConfigParamKind::U16, 1.0, 16)),
+            ]
+        );
+        assert_that!(params.serialized_len(), eq(SERIALIZED.len()));
+    }
+
+    #[test]
+    fn params_read_values() {
+        let params = CustomConfigParams::from_xml(XML).unwrap();
+
+        let values = params
+            .params()
+            .iter()
+            .map(|param| param.read(&SERIALIZED).unwrap())
+            .collect::<Vec<_>>();
+        assert_that!(
+            values,
+            elements_are![
+                eq(&ConfigValue::Float(12.5)),
+                eq(&ConfigValue::Bool(true)),
+                eq(&ConfigValue::Int(2)),
+                eq(&ConfigValue::Float(-2.5)),
+                eq(&ConfigValue::Float(0.25)),
+                eq(&ConfigValue::Int(832)),
+            ]
+        );
+    }
+
+    #[test]
+    fn params_write_values() {
+        let params = CustomConfigParams::from_xml(XML).unwrap();
+        let mut data = SERIALIZED;
+
+        let kp = params.get("kp").unwrap();
+        kp.write(&mut data, ConfigValue::Float(1.5)).unwrap();
+        assert_that!(kp.read(&data), ok(eq(&ConfigValue::Float(1.5))));
+
+        let hz = params.get("hz").unwrap();
+        hz.write(&mut data, ConfigValue::Int(1000)).unwrap();
+        assert_that!(hz.read(&data), ok(eq(&ConfigValue::Int(1000))));
+
+        assert_that!(data[..4], eq(&SERIALIZED[..4]));
+        assert_that!(data[8..16], eq(&SERIALIZED[8..16]));
+    }
+
+    #[test]
+    fn params_reject_string_params() {
+        let xml = XML.replace("<type>4</type>", "<type>3</type>");
+        assert_that!(CustomConfigParams::from_xml(&xml), err(anything()));
+    }
+}
@@ -1,5 +1,8 @@
 use googletest::prelude::*;
-use vesc::{CommandReply, Decoder, Values};
+use vesc::{
+    Command, CommandReply, CustomReply, DecodeCustom, DecodeError, Decoder, DecoderStats,
+    FrameError, SliceDecoder, Values,
+};
 
 #[test]
 fn decoder_decodes_single_packet() {
@@ -23,6 +26,29 @@ fn decoder_decodes_single_packet() {
     assert_that!(decoder.next(), some(expected));
 }
 
+#[test]
+fn decoder_decodes_older_firmware_values() {
+    // A `GetValues` reply that ends before the controller ID.
+    let input = [
+        2, 58, 4, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        119, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 1, 116, 0, 0, 0, 0, 255, 255, 131, 64, 0, 2, 168, 254,
+        0, 18, 6, 65, 224, 116, 219, 3,
+    ];
+
+    let mut decoder = Decoder::default();
+    decoder.feed(&input[..30]).unwrap();
+    assert_that!(decoder.replies().next(), none());
+    decoder.feed(&input[30..]).unwrap();
+
+    let expected = pat!(CommandReply::GetValues(pat!(Values {
+        voltage_in: approx_eq(37.5),
+        controller_id: eq(0),
+        ..
+    })));
+    assert_that!(decoder.replies().next(), some(expected));
+    assert_that!(decoder.stats().bytes_discarded, eq(0));
+}
+
 #[test]
 fn decoder_decodes_packet_fed_in_chunks() {
     let input = [
@@ -34,7 +60,7 @@ fn decoder_decodes_packet_fed_in_chunks() {
     let mut decoder = Decoder::default();
 
     for chunk in input.chunks(5) {
-        decoder.feed(&chunk).unwrap();
+        decoder.feed(chunk).unwrap();
     }
 
     let expected = pat!(CommandReply::GetValues(pat!(Values {
@@ -77,7 +103,7 @@ fn decoder_returns_none_until_packet_is_complete() {
     let mut decoder = Decoder::default();
 
     for (i, chunk) in input.chunks(input.len() / 5).enumerate() {
-        decoder.feed(&chunk).unwrap();
+        decoder.feed(chunk).unwrap();
         if i < 5 {
             assert_that!(decoder.next(), none());
         }
@@ -216,6 +242,27 @@ fn decoder_ignores_unknown_packet_id() {
     assert_that!(decoder.next(), none());
 }
 
+#[test]
+fn decoder_drops_whole_unknown_packet() {
+    let input = [
+        // An unknown packet whose payload holds what looks like a frame.
+        [2, 10, 200, 2, 4, 36, 7, 8, 9, 96, 175, 3, 37, 12, 3].as_slice(),
+        &[2, 4, 36, 1, 2, 3, 156, 142, 3],
+    ]
+    .concat();
+
+    let mut decoder = Decoder::default();
+    decoder.feed(&input).unwrap();
+
+    let replies: Vec<_> = decoder.replies().collect();
+    assert_that!(
+        replies,
+        elements_are![pat!(CommandReply::CustomAppData(eq(&[1, 2, 3].as_slice())))]
+    );
+    assert_that!(decoder.stats().unknown_packets, eq(1));
+    assert_that!(decoder.stats().bytes_discarded, eq(15));
+}
+
 #[test]
 fn decoder_skips_junk_bytes_between_packets() {
     let input = [
@@ -301,6 +348,10 @@ fn decoder_iterator_collects_all_valid_packets() {
     // GetValuesSelective (unknown packet)
     input.extend_from_slice(&[2, 3, 222, 4, 0, 178, 81, 3]);
 
+    // Junk, past the length claimed by a start byte within the bad checksum
+    // frame, so that the decoder stops waiting for the rest of it.
+    input.extend_from_slice(&[0; 128]);
+
     let mut decoder = Decoder::default();
     decoder.feed(&input).unwrap();
 
@@ -342,6 +393,580 @@ fn decoder_iterator_collects_all_valid_packets() {
         }))),
     ];
 
-    let replies = decoder.by_ref().collect::<Vec<_>>();
+    let replies = decoder.replies().collect::<Vec<_>>();
     assert_that!(replies, expected);
 }
+
+#[derive(Debug)]
+struct Speed(u16);
+
+impl DecodeCustom<'_> for Speed {
+    fn decode(payload: &[u8]) -> Result<Self, DecodeError> {
+        let bytes = payload.try_into().map_err(|_| DecodeError::InvalidFrame)?;
+        Ok(Speed(u16::from_be_bytes(bytes)))
+    }
+}
+
+#[test]
+fn decoder_decodes_custom_payloads() {
+    let mut input = vec![];
+
+    // CustomAppData (Speed)
+    input.extend_from_slice(&[2, 3, 36, 7, 208, 8, 236, 3]);
+
+    // GetValuesSelective
+    input.extend_from_slice(&[
+        2, 23, 50, 0, 2, 161, 138, 0, 0, 0, 0, 0, 4, 0, 0, 3, 221, 1, 119, 255, 255, 170, 43, 0,
+        20, 45, 58, 3,
+    ]);
+
+    // CustomAppData (not Speed)
+    input.extend_from_slice(&[2, 2, 36, 9, 91, 11, 3]);
+
+    let mut decoder = Decoder::default();
+    decoder.feed(&input).unwrap();
+
+    let replies = decoder.replies().with_custom::<Speed>().collect::<Vec<_>>();
+    assert_that!(replies.len(), eq(3));
+    assert_that!(
+        replies[0],
+        pat!(CustomReply::Custom(pat!(Speed(eq(&2000)))))
+    );
+    assert_that!(
+        replies[1],
+        pat!(CustomReply::Reply(pat!(CommandReply::GetValuesSelective(
+            pat!(Values {
+                rpm: approx_eq(989.0),
+                controller_id: eq(&20),
+                ..
+            })
+        ))))
+    );
+    assert_that!(
+        replies[2],
+        pat!(CustomReply::Reply(pat!(CommandReply::CustomAppData(eq(
+            &[9].as_slice()
+        )))))
+    );
+}
+
+#[test]
+fn decoder_counts_link_errors() {
+    let input = [
+        [0xff, 0x00].as_slice(),
+        // A frame with a corrupted checksum.
+        &[2, 4, 36, 7, 8, 9, 96, 176, 3],
+        // A frame with an unknown packet ID.
+        &[2, 1, 200, 88, 68, 3],
+        &[2, 4, 36, 7, 8, 9, 96, 175, 3],
+    ]
+    .concat();
+
+    let mut decoder = Decoder::default();
+    assert_that!(decoder.stats(), eq(DecoderStats::default()));
+    assert_that!(decoder.last_error(), none());
+
+    decoder.feed(&input).unwrap();
+    let replies: Vec<_> = decoder.replies().collect();
+    assert_that!(
+        replies,
+        elements_are![pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice())))]
+    );
+
+    let expected = DecoderStats {
+        frames_decoded: 1,
+        bytes_discarded: 17,
+        checksum_mismatches: 1,
+        unknown_packets: 1,
+        buffer_resets: 0,
+    };
+    assert_that!(decoder.stats(), eq(expected));
+    assert_that!(
+        decoder.last_error(),
+        some(eq(&DecodeError::UnknownPacket { id: 200 }))
+    );
+
+    decoder.reset_stats();
+    assert_that!(decoder.stats(), eq(DecoderStats::default()));
+    assert_that!(decoder.last_error(), none());
+}
+
+#[test]
+fn decoder_try_replies_reports_skipped_frames() {
+    let input = [
+        [0xff, 0x00].as_slice(),
+        // A frame with a corrupted checksum.
+        &[2, 4, 36, 7, 8, 9, 96, 176, 3],
+        // A frame with an unknown packet ID.
+        &[2, 1, 200, 88, 68, 3],
+        &[2, 4, 36, 7, 8, 9, 96, 175, 3],
+    ]
+    .concat();
+
+    let mut decoder = Decoder::default();
+    decoder.feed(&input).unwrap();
+
+    let replies: Vec<_> = decoder.try_replies().collect();
+    assert_that!(
+        replies,
+        elements_are![
+            err(eq(&FrameError {
+                error: DecodeError::ChecksumMismatch {
+                    expected: 0x60b0,
+                    actual: 0x60af,
+                },
+                range: 2..11,
+                bytes: &[2, 4, 36, 7, 8, 9, 96, 176, 3],
+            })),
+            err(eq(&FrameError {
+                error: DecodeError::UnknownPacket { id: 200 },
+                range: 11..17,
+                bytes: &[2, 1, 200, 88, 68, 3],
+            })),
+            ok(pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice())))),
+        ]
+    );
+
+    let expected = DecoderStats {
+        frames_decoded: 1,
+        bytes_discarded: 17,
+        checksum_mismatches: 1,
+        unknown_packets: 1,
+        buffer_resets: 0,
+    };
+    assert_that!(decoder.stats(), eq(expected));
+}
+
+#[test]
+fn decoder_try_replies_waits_for_complete_unknown_packet() {
+    let input = [
+        [2, 4, 36, 7, 8, 9, 96, 175, 3].as_slice(),
+        &[2, 1, 200, 88, 68, 3],
+    ]
+    .concat();
+
+    let mut decoder = Decoder::<16>::new();
+    decoder.feed(&input[..12]).unwrap();
+    let replies: Vec<_> = decoder.try_replies().collect();
+    assert_that!(replies, elements_are![ok(anything())]);
+
+    // The range counts the bytes fed before, even once they are discarded.
+    decoder.feed(&input[12..]).unwrap();
+    let replies: Vec<_> = decoder.try_replies().collect();
+    assert_that!(
+        replies,
+        elements_are![err(field!(FrameError.range, eq(&(9..15))))]
+    );
+}
+
+#[test]
+fn decoder_try_replies_resyncs_after_false_start() {
+    let input = [
+        // A false start byte claiming a 200-byte payload, which cannot fit.
+        [2, 200, 36].as_slice(),
+        &[2, 4, 36, 7, 8, 9, 96, 175, 3],
+    ]
+    .concat();
+
+    let mut decoder = Decoder::<64>::new();
+    decoder.feed(&input).unwrap();
+
+    let replies: Vec<_> = decoder.try_replies().collect();
+    assert_that!(
+        replies,
+        elements_are![ok(pat!(CommandReply::CustomAppData(eq(
+            &[7, 8, 9].as_slice()
+        ))))]
+    );
+    assert_that!(decoder.stats().bytes_discarded, eq(3));
+    assert_that!(decoder.last_error(), some(eq(&DecodeError::InvalidFrame)));
+}
+
+#[test]
+fn decoder_waits_for_frame_with_frame_in_payload() {
+    let mut data = [1u8; 64];
+    data[20..29].copy_from_slice(&[2, 4, 36, 7, 8, 9, 96, 175, 3]);
+    let mut input = [0u8; 70];
+    let len = vesc::encode(Command::CustomAppData(&data), &mut input).unwrap();
+
+    let mut decoder = Decoder::default();
+    let mut decoded = 0;
+    for chunk in input[..len].chunks(8) {
+        decoder.feed(chunk).unwrap();
+        for reply in decoder.replies() {
+            assert_that!(
+                reply,
+                pat!(CommandReply::CustomAppData(eq(data.as_slice())))
+            );
+            decoded += 1;
+        }
+    }
+
+    assert_that!(decoded, eq(1));
+    assert_that!(decoder.stats().bytes_discarded, eq(0));
+}
+
+#[test]
+fn decoder_with_raw_passes_through_unknown_packets() {
+    let input = [
+        [2, 2, 200, 1, 143, 220, 3].as_slice(),
+        &[2, 4, 36, 7, 8, 9, 96, 175, 3],
+    ]
+    .concat();
+
+    let mut decoder = Decoder::default();
+    decoder.feed(&input).unwrap();
+
+    let replies: Vec<_> = decoder.replies().with_raw().collect();
+    assert_that!(
+        replies,
+        elements_are![
+            pat!(CommandReply::Raw {
+                id: eq(&200),
+                payload: eq(&[1].as_slice()),
+            }),
+            pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice()))),
+        ]
+    );
+    assert_that!(decoder.stats().frames_decoded, eq(2));
+    assert_that!(decoder.stats().bytes_discarded, eq(0));
+}
+
+#[test]
+fn decoder_try_replies_with_raw_passes_through_unknown_packets() {
+    let input = [2, 2, 200, 1, 143, 220, 3];
+
+    let mut decoder = Decoder::default();
+    decoder.feed(&input).unwrap();
+
+    let replies: Vec<_> = decoder.try_replies().with_raw().collect();
+    assert_that!(
+        replies,
+        elements_are![ok(pat!(CommandReply::Raw {
+            id: eq(&200),
+            payload: eq(&[1].as_slice()),
+        }))]
+    );
+}
+
+#[test]
+fn decoder_decodes_packets_reusing_buffer() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    // Every other frame only fits once the partial frame before it is moved
+    // to the front of the 16-byte buffer.
+    let mut decoder = Decoder::<16>::new();
+    for _ in 0..5 {
+        for chunk in input.chunks(4) {
+            decoder.feed(chunk).unwrap();
+        }
+        let replies: Vec<_> = decoder.replies().collect();
+        assert_that!(
+            replies,
+            elements_are![pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice())))]
+        );
+    }
+    assert_that!(decoder.stats().frames_decoded, eq(5));
+}
+
+#[test]
+fn decoder_fills_whole_buffer_after_draining() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    let mut decoder = Decoder::<9>::new();
+    decoder.feed(&input[..5]).unwrap();
+    decoder.feed(&[0xff; 4]).unwrap();
+    assert_that!(decoder.replies().next(), none());
+
+    // The junk after the incomplete frame made it invalid, so the buffer
+    // must have been drained and can hold a whole frame again.
+    assert_that!(decoder.feed(&input), ok(eq(&9)));
+    let replies: Vec<_> = decoder.replies().collect();
+    assert_that!(
+        replies,
+        elements_are![pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice())))]
+    );
+}
+
+#[test]
+fn decoder_skips_junk_up_to_next_start_byte() {
+    let input = [
+        [10, 34, 12, 4, 178, 255].as_slice(),
+        &[2, 4, 36, 7, 8, 9, 96, 175, 3],
+    ]
+    .concat();
+
+    let mut decoder = Decoder::default();
+    decoder.feed(&input).unwrap();
+
+    let replies: Vec<_> = decoder.replies().collect();
+    assert_that!(replies, len(eq(1)));
+    assert_that!(decoder.stats().bytes_discarded, eq(6));
+    assert_that!(decoder.last_error(), some(eq(&DecodeError::InvalidFrame)));
+}
+
+#[test]
+fn decoder_try_replies_reports_stream_range() {
+    let valid = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+    let unknown = [2, 1, 200, 88, 68, 3];
+
+    let mut decoder = Decoder::<12>::new();
+    decoder.feed(&valid).unwrap();
+    assert_that!(decoder.try_replies().count(), eq(1));
+
+    decoder.feed(&unknown).unwrap();
+    let replies: Vec<_> = decoder.try_replies().collect();
+    assert_that!(
+        replies,
+        elements_are![err(eq(&FrameError {
+            error: DecodeError::UnknownPacket { id: 200 },
+            range: 9..15,
+            bytes: &unknown,
+        }))]
+    );
+}
+
+#[test]
+fn slice_decoder_decodes_packets_reusing_buffer() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    let mut storage = [0u8; 16];
+    let mut decoder = SliceDecoder::new(&mut storage);
+    assert_that!(decoder.capacity(), eq(16));
+
+    for _ in 0..5 {
+        for chunk in input.chunks(4) {
+            decoder.feed(chunk).unwrap();
+        }
+        let replies: Vec<_> = (&mut decoder).into_iter().collect();
+        assert_that!(
+            replies,
+            elements_are![pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice())))]
+        );
+    }
+    assert_that!(decoder.stats().frames_decoded, eq(5));
+}
+
+#[test]
+fn slice_decoder_counts_link_errors() {
+    let input = [
+        [0xff, 0x00].as_slice(),
+        &[2, 4, 36, 7, 8, 9, 96, 176, 3],
+        &[2, 4, 36, 7, 8, 9, 96, 175, 3],
+    ]
+    .concat();
+
+    let mut storage = vec![0u8; 128];
+    let mut decoder = SliceDecoder::new(&mut storage);
+    decoder.feed(&input).unwrap();
+
+    let replies: Vec<_> = decoder.try_replies().collect();
+    assert_that!(replies, elements_are![err(anything()), ok(anything())]);
+    assert_that!(decoder.stats().checksum_mismatches, eq(1));
+    assert_that!(decoder.stats().bytes_discarded, eq(11));
+
+    decoder.reset_stats();
+    assert_that!(decoder.last_error(), none());
+}
+
+#[test]
+fn slice_decoder_over_empty_storage() {
+    let mut decoder = SliceDecoder::new(&mut []);
+
+    assert_that!(decoder.feed(&[2, 4, 36]), ok(eq(&0)));
+    assert_that!(decoder.replies().next(), none());
+    assert_that!(decoder.stats(), eq(DecoderStats::default()));
+}
+
+#[test]
+fn decoder_decodes_packets_written_in_place() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    // Frames are written in uneven chunks, so that a partial frame is often
+    // buffered when the free space after it runs out.
+    let input = input.repeat(10);
+    let mut decoder = Decoder::<16>::new();
+    let mut decoded = 0;
+    let mut written = 0;
+    while written < input.len() {
+        let free = decoder.write_buf();
+        let len = free.len().min(7).min(input.len() - written);
+        free[..len].copy_from_slice(&input[written..written + len]);
+        decoder.commit(len);
+        written += len;
+        decoded += decoder.replies().count();
+    }
+
+    assert_that!(decoded, eq(10));
+    assert_that!(decoder.stats().bytes_discarded, eq(0));
+}
+
+#[test]
+fn decoder_offers_whole_free_space() {
+    let mut decoder = Decoder::<16>::new();
+    assert_that!(decoder.write_buf().len(), eq(16));
+
+    decoder.feed(&[2, 4, 36, 7, 8]).unwrap();
+    assert_that!(decoder.write_buf().len(), eq(11));
+
+    decoder.feed(&[9, 96, 175, 3]).unwrap();
+    assert_that!(decoder.replies().count(), eq(1));
+    assert_that!(decoder.write_buf().len(), eq(16));
+}
+
+#[test]
+fn decoder_offers_free_space_after_partial_frame() {
+    let mut decoder = Decoder::<16>::new();
+    decoder
+        .feed(&[2, 4, 36, 7, 8, 9, 96, 175, 3, 2, 4, 36])
+        .unwrap();
+    assert_that!(decoder.replies().count(), eq(1));
+
+    // The partial frame stays in place while there is space after it.
+    let free = decoder.write_buf();
+    assert_that!(free.len(), eq(4));
+    free.copy_from_slice(&[7, 8, 9, 96]);
+    decoder.commit(4);
+
+    // Once that space is used up, the space at the front of the buffer is
+    // offered.
+    let free = decoder.write_buf();
+    assert_that!(free.len(), eq(9));
+    free[..2].copy_from_slice(&[175, 3]);
+    decoder.commit(2);
+    assert_that!(decoder.replies().count(), eq(1));
+}
+
+#[test]
+fn decoder_decodes_written_bytes_once_committed() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    let mut decoder = Decoder::<16>::new();
+    decoder.feed(&input[..5]).unwrap();
+    decoder.write_buf()[..4].copy_from_slice(&input[5..]);
+    assert_that!(decoder.replies().count(), eq(0));
+
+    decoder.commit(4);
+    assert_that!(decoder.replies().count(), eq(1));
+}
+
+#[test]
+fn decoder_decodes_frame_across_end_of_buffer() {
+    let mut decoder = Decoder::<32>::new();
+    decoder.feed(&[0; 27]).unwrap();
+    decoder.feed(&[2, 4, 36]).unwrap();
+    assert_that!(decoder.replies().next(), none());
+
+    // The rest of the frame wraps around to the front of the buffer.
+    assert_that!(decoder.write_buf().len(), eq(2));
+    decoder.feed(&[7, 8, 9, 96, 175, 3]).unwrap();
+    assert_that!(
+        decoder.next(),
+        some(pat!(CommandReply::CustomAppData(eq([7, 8, 9].as_slice()))))
+    );
+    assert_that!(decoder.stats().bytes_discarded, eq(27));
+    assert_that!(decoder.write_buf().len(), eq(32));
+}
+
+#[test]
+fn decoder_try_replies_reports_frame_across_end_of_buffer() {
+    let mut decoder = Decoder::<32>::new();
+    decoder.feed(&[0; 27]).unwrap();
+    decoder.feed(&[2, 4, 36]).unwrap();
+    assert_that!(decoder.replies().next(), none());
+
+    // A corrupted frame, followed by one that is fine.
+    decoder.feed(&[7, 8, 9, 96, 176, 3]).unwrap();
+    decoder.feed(&[2, 4, 36, 7, 8, 9, 96, 175, 3]).unwrap();
+
+    let replies: Vec<_> = decoder.try_replies().collect();
+    assert_that!(
+        replies,
+        elements_are![
+            err(pat!(FrameError {
+                error: pat!(DecodeError::ChecksumMismatch { .. }),
+                range: eq(&(27..36)),
+                bytes: eq(&[2, 4, 36, 7, 8, 9, 96, 176, 3].as_slice()),
+            })),
+            ok(pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice())))),
+        ]
+    );
+}
+
+#[test]
+fn decoder_decodes_frame_across_end_of_full_buffer() {
+    let mut decoder = Decoder::<16>::new();
+    decoder
+        .feed(&[2, 4, 36, 7, 8, 9, 96, 175, 3, 2, 4, 36])
+        .unwrap();
+    assert_that!(decoder.replies().count(), eq(1));
+
+    // The frame takes up more than half of the buffer once it wraps around.
+    decoder.feed(&[7, 8, 9, 96, 175, 3]).unwrap();
+    assert_that!(
+        decoder.next(),
+        some(pat!(CommandReply::CustomAppData(eq([7, 8, 9].as_slice()))))
+    );
+    assert_that!(decoder.stats().bytes_discarded, eq(0));
+}
+
+#[test]
+#[should_panic(expected = "committed more bytes than were free")]
+fn decoder_rejects_commit_beyond_free_space() {
+    let mut decoder = Decoder::<16>::new();
+    decoder.feed(&[2, 4, 36, 7, 8]).unwrap();
+    decoder.commit(12);
+}
+
+#[test]
+fn slice_decoder_decodes_packets_written_in_place() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    let mut storage = [0u8; 32];
+    let mut decoder = SliceDecoder::new(&mut storage);
+    let mut decoded = 0;
+    let input = input.repeat(10);
+    let mut written = 0;
+    while written < input.len() {
+        let free = decoder.write_buf();
+        let len = free.len().min(5).min(input.len() - written);
+        free[..len].copy_from_slice(&input[written..written + len]);
+        decoder.commit(len);
+        written += len;
+        decoded += decoder.replies().count();
+    }
+    assert_that!(decoded, eq(10));
+}
+
+#[test]
+fn decoder_decodes_largest_frame_by_default() {
+    // A payload of 512 bytes, the most the firmware sends.
+    let data = [7u8; 511];
+    let mut input = [0u8; Command::MAX_FRAME_LEN];
+    let len = vesc::encode(Command::CustomAppData(&data), &mut input).unwrap();
+    assert_that!(len, eq(Command::MAX_FRAME_LEN));
+
+    let mut decoder = Decoder::default();
+    for chunk in input.chunks(64) {
+        decoder.feed(chunk).unwrap();
+    }
+
+    assert_that!(
+        decoder.next(),
+        some(pat!(CommandReply::CustomAppData(eq(data.as_slice()))))
+    );
+    assert_that!(decoder.stats().buffer_resets, eq(0));
+}
+
+#[test]
+fn decoder_counts_buffer_resets() {
+    let mut decoder = Decoder::<8>::new();
+
+    // The start of a frame that cannot fit into the buffer.
+    decoder.feed(&[2, 100, 50, 0, 0, 0, 0, 0]).unwrap();
+    decoder.feed(&[2]).unwrap();
+
+    let stats = decoder.stats();
+    assert_that!(stats.buffer_resets, eq(1));
+    assert_that!(stats.bytes_discarded, eq(8));
+}
@@ -0,0 +1,132 @@
+//! Checks the `float32_auto` encoding against a port of the firmware's
+//! `buffer_append_float32_auto` and `buffer_get_float32_auto`, through the
+//! custom config parameters that use it.
+
+use googletest::prelude::*;
+use proptest::prelude::*;
+
+use vesc::{ConfigParamKind, ConfigValue, EncodeError};
+
+/// The firmware's `buffer_append_float32_auto`, with `frexpf` spelled out.
+fn firmware_append(number: f32) -> u32 {
+    let number = if number.abs() < 1.5e-38 { 0.0 } else { number };
+
+    let mut e = 0;
+    let mut sig = f64::from(number);
+    if sig != 0.0 {
+        while sig.abs() >= 1.0 {
+            sig /= 2.0;
+            e += 1;
+        }
+        while sig.abs() < 0.5 {
+            sig *= 2.0;
+            e -= 1;
+        }
+    }
+    let sig = sig as f32;
+    let sig_abs = sig.abs();
+
+    let mut sig_i = 0;
+    if sig_abs >= 0.5 {
+        sig_i = ((sig_abs - 0.5) * 2.0 * 8_388_608.0) as u32;
+        e += 126;
+    }
+
+    let mut res = ((e as u32 & 0xff) << 23) | (sig_i & 0x7f_ffff);
+    if sig < 0.0 {
+        res |= 1 << 31;
+    }
+    res
+}
+
+/// The firmware's `buffer_get_float32_auto`. Scaling in double precision and
+/// converting once rounds the same way as `ldexpf`.
+fn firmware_get(res: u32) -> f32 {
+    let mut e = ((res >> 23) & 0xff) as i32;
+    let sig_i = res & 0x7f_ffff;
+    let neg = res & (1 << 31) != 0;
+
+    let mut sig = 0.0;
+    if e != 0 || sig_i != 0 {
+        sig = f64::from(sig_i) / (8_388_608.0 * 2.0) + 0.5;
+        e -= 126;
+    }
+    if neg {
+        sig = -sig;
+    }
+    (sig * 2f64.powi(e)) as f32
+}
+
+fn write(value: f32) -> std::result::Result<u32, EncodeError> {
+    let mut data = [0u8; 4];
+    ConfigParamKind::F32Auto.write(&mut data, 0, 1.0, ConfigValue::Float(value))?;
+    Ok(u32::from_be_bytes(data))
+}
+
+fn read(bits: u32) -> f32 {
+    match ConfigParamKind::F32Auto.read(&bits.to_be_bytes(), 0, 1.0) {
+        Ok(ConfigValue::Float(value)) => value,
+        other => panic!("unexpected result {other:?}"),
+    }
+}
+
+#[test]
+fn encodes_zero_and_flushes_small_values() {
+    for value in [0.0, -0.0, 1e-45, -1e-40, f32::MIN_POSITIVE, -1.4999999e-38] {
+        assert_that!(write(value), ok(eq(&0)));
+    }
+    assert_that!(write(1.5e-38), ok(eq(&1.5e-38f32.to_bits())));
+}
+
+#[test]
+fn encodes_normal_values_unchanged() {
+    for value in [1.0, -2.5, 0.1, f32::MAX, f32::MIN, 123_456.79] {
+        assert_that!(write(value), ok(eq(&value.to_bits())));
+    }
+}
+
+#[test]
+fn rejects_nan_and_infinities() {
+    for value in [f32::NAN, -f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert_that!(write(value), err(eq(&EncodeError::ValueOutOfRange)));
+    }
+}
+
+#[test]
+fn decodes_zero_exponent_as_subnormal() {
+    assert_that!(read(0).to_bits(), eq(0));
+    assert_that!(read(1 << 31).to_bits(), eq((-0.0f32).to_bits()));
+    assert_that!(read(0x0040_0000), eq(0.75 * f32::MIN_POSITIVE));
+    assert_that!(read(0x8000_0001), eq(-0.5 * f32::MIN_POSITIVE));
+}
+
+#[test]
+fn decodes_max_exponent_as_infinity() {
+    assert_that!(read(0x7f80_0000), eq(f32::INFINITY));
+    assert_that!(read(0x7fc0_0000), eq(f32::INFINITY));
+    assert_that!(read(0xff80_0001), eq(f32::NEG_INFINITY));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10_000))]
+
+    // Drawing the bits rather than the value covers every exponent evenly.
+    #[test]
+    fn write_matches_firmware(bits in any::<u32>()) {
+        let value = f32::from_bits(bits);
+        prop_assume!(value.is_finite());
+        prop_assert_eq!(write(value), Ok(firmware_append(value)));
+    }
+
+    #[test]
+    fn read_matches_firmware(bits in any::<u32>()) {
+        prop_assert_eq!(read(bits).to_bits(), firmware_get(bits).to_bits());
+    }
+
+    #[test]
+    fn normal_values_round_trip(bits in any::<u32>()) {
+        let value = f32::from_bits(bits);
+        prop_assume!(value.is_finite() && value.abs() >= 1.5e-38);
+        prop_assert_eq!(read(write(value).unwrap()).to_bits(), bits);
+    }
+}
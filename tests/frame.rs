@@ -0,0 +1,155 @@
+use googletest::prelude::*;
+use vesc::{CommandReply, DecodeError, EncodeError, Frame};
+
+#[test]
+fn parse_short_frame() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 3, 0xff];
+
+    let frame = Frame::parse(&input).unwrap();
+    assert_that!(frame.as_bytes(), eq(&input[..9]));
+    assert_that!(frame.payload(), eq(&[36, 7, 8, 9]));
+    assert_that!(frame.packet_id(), some(eq(36)));
+}
+
+#[test]
+fn parse_long_frame() {
+    let input = [[3, 1, 45, 201].as_slice(), &[0xaa; 300], &[62, 127, 3]].concat();
+
+    let frame = Frame::parse(&input).unwrap();
+    assert_that!(frame.as_bytes().len(), eq(307));
+    assert_that!(frame.payload().len(), eq(301));
+    assert_that!(frame.packet_id(), some(eq(201)));
+}
+
+#[test]
+fn parse_empty_frame() {
+    let frame = Frame::parse(&[2, 0, 0, 0, 3]).unwrap();
+    assert_that!(frame.payload(), is_empty());
+    assert_that!(frame.packet_id(), none());
+}
+
+#[test]
+fn parse_incomplete_frame() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175];
+
+    let expected = &DecodeError::IncompleteData;
+    assert_that!(Frame::parse(&input), err(eq(expected)));
+}
+
+#[test]
+fn parse_checksum_mismatch() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 176, 3];
+
+    let expected = &DecodeError::ChecksumMismatch {
+        expected: 0x60b0,
+        actual: 0x60af,
+    };
+    assert_that!(Frame::parse(&input), err(eq(expected)));
+}
+
+#[test]
+fn parse_invalid_frame_start() {
+    let input = [4, 4, 36, 7, 8, 9, 96, 175, 3];
+
+    let expected = &DecodeError::InvalidFrame;
+    assert_that!(Frame::parse(&input), err(eq(expected)));
+}
+
+#[test]
+fn parse_invalid_frame_end() {
+    let input = [2, 4, 36, 7, 8, 9, 96, 175, 4];
+
+    let expected = &DecodeError::InvalidFrame;
+    assert_that!(Frame::parse(&input), err(eq(expected)));
+}
+
+#[test]
+fn parse_payload_len_too_large() {
+    let input = [[3, 2, 1, 201].as_slice(), &[0; 515]].concat();
+
+    let expected = &DecodeError::InvalidFrame;
+    assert_that!(Frame::parse(&input), err(eq(expected)));
+}
+
+#[test]
+fn parse_checks_length_before_payload() {
+    // The length byte claims one byte more than the payload, which would
+    // otherwise make a checksum byte part of it.
+    let input = [2, 5, 36, 7, 8, 9, 96, 175, 3];
+
+    let expected = &DecodeError::IncompleteData;
+    assert_that!(Frame::parse(&input), err(eq(expected)));
+}
+
+#[test]
+fn reply_known_packet() {
+    let frame = Frame::parse(&[2, 4, 36, 7, 8, 9, 96, 175, 3]).unwrap();
+
+    assert_that!(
+        frame.reply(),
+        ok(pat!(CommandReply::CustomAppData(eq(&[7, 8, 9].as_slice()))))
+    );
+}
+
+#[test]
+fn reply_unknown_packet() {
+    let frame = Frame::parse(&[2, 2, 200, 1, 143, 220, 3]).unwrap();
+
+    let expected = &DecodeError::UnknownPacket { id: 200 };
+    assert_that!(frame.reply(), err(eq(expected)));
+}
+
+#[test]
+fn reply_invalid_payload() {
+    // A selective values reply with a trailing byte after the empty mask.
+    let frame = Frame::parse(&[2, 6, 50, 0, 0, 0, 0, 0, 164, 204, 3]).unwrap();
+
+    let expected = &DecodeError::InvalidFrame;
+    assert_that!(frame.reply(), err(eq(expected)));
+}
+
+#[test]
+fn write_short_frame() {
+    let mut buf = [0u8; 16];
+
+    let len = Frame::write(&[36, 7, 8, 9], &mut buf).unwrap();
+    assert_that!(buf[..len], eq(&[2, 4, 36, 7, 8, 9, 96, 175, 3]));
+}
+
+#[test]
+fn write_long_frame() {
+    let payload = [[201].as_slice(), &[0xaa; 300]].concat();
+    let mut buf = [0u8; 512];
+
+    let len = Frame::write(&payload, &mut buf).unwrap();
+    let expected = [[3, 1, 45, 201].as_slice(), &[0xaa; 300], &[62, 127, 3]].concat();
+    assert_that!(buf[..len], eq(&expected[..]));
+}
+
+#[test]
+fn write_buffer_too_small() {
+    let mut buf = [0u8; 8];
+
+    let expected = &EncodeError::BufferTooSmall;
+    assert_that!(Frame::write(&[36, 7, 8, 9], &mut buf), err(eq(expected)));
+}
+
+#[test]
+fn write_payload_too_large() {
+    let mut buf = [0u8; 1024];
+
+    // The firmware accepts payloads of up to 512 bytes.
+    assert_that!(Frame::write(&[0; 512], &mut buf), ok(eq(&518)));
+
+    let expected = &EncodeError::PayloadTooLarge;
+    assert_that!(Frame::write(&[0; 513], &mut buf), err(eq(expected)));
+}
+
+#[test]
+fn write_then_parse() {
+    let mut buf = [0u8; 16];
+
+    let len = Frame::write(&[200, 1, 2, 3], &mut buf).unwrap();
+    let frame = Frame::parse(&buf[..len]).unwrap();
+    assert_that!(frame.payload(), eq(&[200, 1, 2, 3]));
+}
@@ -0,0 +1,186 @@
+use googletest::prelude::*;
+
+use vesc::{Command, OwnedCommand, OwnedCommandError, OwnedCommandKind, ValuesMask};
+
+fn encode(command: Command) -> Vec<u8> {
+    let mut buf = [0u8; Command::MAX_FRAME_LEN];
+    let len = vesc::encode(command, &mut buf).unwrap();
+    buf[..len].to_vec()
+}
+
+fn encode_owned<const DEPTH: usize, const LEN: usize>(
+    command: &OwnedCommand<DEPTH, LEN>,
+) -> Vec<u8> {
+    let mut buf = [0u8; Command::MAX_FRAME_LEN];
+    let len = command.encode(&mut buf).unwrap();
+    buf[..len].to_vec()
+}
+
+#[test]
+fn owned_command_from_command() {
+    let commands = [
+        Command::GetValues,
+        Command::SetCurrent(-2.5),
+        Command::SetRpm(1500),
+        Command::GetValuesSelective(ValuesMask::RPM | ValuesMask::STATUS),
+        Command::LispReadCode {
+            len: 128,
+            offset: 256,
+        },
+        Command::GetCustomConfigXml {
+            index: 1,
+            len: 400,
+            offset: 800,
+        },
+        Command::CanFwdFrame {
+            id: 0x123,
+            extended: false,
+            data: &[1, 2, 3],
+        },
+    ];
+
+    for command in commands {
+        let owned = OwnedCommand::<1>::try_from(command).unwrap();
+        assert_that!(owned.can_path(), is_empty());
+        assert_that!(encode_owned(&owned), eq(&encode(command)));
+    }
+}
+
+#[test]
+fn owned_command_keeps_can_path() {
+    let command = Command::ForwardCan(3, &Command::ForwardCan(7, &Command::SetRpm(-1500)));
+
+    let owned = OwnedCommand::<2>::try_from(command).unwrap();
+    assert_that!(owned.can_path(), eq(&[3, 7]));
+    assert_that!(owned.command(), eq(&OwnedCommandKind::SetRpm(-1500)));
+    assert_that!(encode_owned(&owned), eq(&encode(command)));
+    assert_that!(owned.encoded_len(), ok(eq(&encode(command).len())));
+}
+
+#[test]
+fn owned_command_forward_can() {
+    let owned = OwnedCommand::<2>::new(OwnedCommandKind::GetValues)
+        .forward_can(7)
+        .unwrap()
+        .forward_can(3)
+        .unwrap();
+
+    let command = Command::ForwardCan(3, &Command::ForwardCan(7, &Command::GetValues));
+    assert_that!(owned.can_path(), eq(&[3, 7]));
+    assert_that!(encode_owned(&owned), eq(&encode(command)));
+
+    let expected = &OwnedCommandError::TooDeep;
+    assert_that!(owned.forward_can(1), err(eq(expected)));
+}
+
+#[test]
+fn owned_command_with_command() {
+    let owned = OwnedCommand::<1>::new(OwnedCommandKind::LispSetRunning(true))
+        .forward_can(5)
+        .unwrap();
+
+    let is_forwarded = owned.with_command(|command| {
+        matches!(
+            command,
+            Command::ForwardCan(5, Command::LispSetRunning(true))
+        )
+    });
+    assert_that!(is_forwarded, eq(true));
+}
+
+#[test]
+fn owned_command_too_deep() {
+    let command = Command::ForwardCan(3, &Command::ForwardCan(7, &Command::GetValues));
+
+    let expected = &OwnedCommandError::TooDeep;
+    assert_that!(OwnedCommand::<1>::try_from(command), err(eq(expected)));
+}
+
+#[test]
+fn owned_command_with_data() {
+    let commands = [
+        Command::LispWriteCode {
+            offset: 64,
+            data: &[1, 2, 3],
+        },
+        Command::LispReplCmd("(+ 1 2)"),
+        Command::LispStreamCode {
+            offset: 0,
+            total_len: 3,
+            restart: true,
+            data: &[1, 2, 3],
+        },
+        Command::FileList {
+            path: "/logs",
+            from: Some("a.log"),
+        },
+        Command::FileRead {
+            path: "/a.log",
+            offset: 400,
+        },
+        Command::FileWrite {
+            path: "/a",
+            offset: 0,
+            total_len: 3,
+            data: &[1, 2, 3],
+        },
+        Command::FileMkdir("/logs"),
+        Command::FileRemove("/a.log"),
+        Command::SetCustomConfig {
+            index: 0,
+            data: &[1, 2, 3],
+        },
+        Command::CustomAppData(&[1, 2, 3]),
+        Command::ForwardCan(3, &Command::FileMkdir("/logs")),
+    ];
+
+    for command in commands {
+        let owned = OwnedCommand::<1, 8>::try_from(command).unwrap();
+        assert_that!(encode_owned(&owned), eq(&encode(command)));
+    }
+}
+
+#[test]
+fn owned_command_too_long() {
+    let commands = [
+        Command::LispReplCmd("(+ 1 2 3)"),
+        Command::CustomAppData(&[0; 9]),
+        Command::FileList {
+            path: "/logs",
+            from: Some("long-name.log"),
+        },
+        Command::ForwardCan(3, &Command::FileMkdir("/logs/old")),
+    ];
+
+    for command in commands {
+        let expected = &OwnedCommandError::TooLong;
+        assert_that!(OwnedCommand::<1, 8>::try_from(command), err(eq(expected)));
+    }
+}
+
+#[test]
+fn owned_command_invalid_can_frame() {
+    let command = Command::CanFwdFrame {
+        id: 0x123,
+        extended: false,
+        data: &[0; 9],
+    };
+
+    let expected = &OwnedCommandError::InvalidCanFrame;
+    assert_that!(OwnedCommand::<1>::try_from(command), err(eq(expected)));
+}
+
+#[test]
+fn owned_command_across_threads() {
+    let (sender, receiver) = std::sync::mpsc::channel::<OwnedCommand>();
+
+    let handle = std::thread::spawn(move || {
+        let command = Command::ForwardCan(3, &Command::SetCurrent(1.0));
+        sender.send(command.try_into().unwrap()).unwrap();
+    });
+    handle.join().unwrap();
+
+    let owned = receiver.recv().unwrap();
+    let command = Command::ForwardCan(3, &Command::SetCurrent(1.0));
+    assert_that!(encode_owned(&owned), eq(&encode(command)));
+}
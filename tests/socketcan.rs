@@ -0,0 +1,110 @@
+//! These tests need a virtual CAN interface, which can be set up with:
+//!
+//! ```sh
+//! ip link add dev vcan0 type vcan
+//! ip link set up vcan0
+//! ```
+#![cfg(all(feature = "socketcan", target_os = "linux"))]
+
+use std::io;
+use std::time::Duration;
+
+use googletest::prelude::*;
+
+use vesc::CommandReply;
+use vesc::can::{self, CanFrame, Command, HwType, Message, Received, SocketCan, Status};
+
+#[test]
+fn open_unknown_interface() {
+    let result = SocketCan::open("vesc-unknown0", 1, &[42]);
+    assert_that!(result, err(anything()));
+}
+
+#[test]
+#[ignore = "requires a vcan0 interface"]
+fn set_zero_read_timeout() {
+    let socket = SocketCan::open("vcan0", 1, &[42]).unwrap();
+
+    let result = socket.set_read_timeout(Some(Duration::ZERO));
+    assert_that!(
+        result.map_err(|err| err.kind()),
+        err(eq(io::ErrorKind::InvalidInput))
+    );
+    assert_that!(socket.set_read_timeout(None), ok(anything()));
+}
+
+#[test]
+#[ignore = "requires a vcan0 interface"]
+fn send_commands() {
+    let host = SocketCan::open("vcan0", 1, &[42]).unwrap();
+    let vesc = SocketCan::open("vcan0", 42, &[]).unwrap();
+
+    host.send(42, vesc::Command::GetValues).unwrap();
+    let frame = vesc.recv_frame().unwrap();
+    assert_that!(frame, eq(CanFrame::new(0x082a, &[1, 0, 4]).unwrap()));
+
+    host.send_can(42, Command::SetRpm(1500)).unwrap();
+    let frame = vesc.recv_frame().unwrap();
+    assert_that!(frame, eq(CanFrame::new(0x032a, &[0, 0, 5, 220]).unwrap()));
+}
+
+#[test]
+#[ignore = "requires a vcan0 interface"]
+fn receive_replies_and_status() {
+    let mut host = SocketCan::open("vcan0", 1, &[42]).unwrap();
+    let vesc = SocketCan::open("vcan0", 42, &[]).unwrap();
+
+    // Filtered out, since controller 43 was not asked for.
+    let status = can::encode(43, Command::SetRpm(0)).unwrap();
+    vesc.send_frame(&CanFrame::new(0x092b, status.data()).unwrap())
+        .unwrap();
+
+    let status = CanFrame::new(0x092a, &[0, 0, 5, 220, 0, 15, 1, 244]).unwrap();
+    vesc.send_frame(&status).unwrap();
+    let received = host.recv().unwrap();
+    assert_that!(
+        received,
+        some(pat!(Received::Message(
+            eq(&42),
+            pat!(Message::Status(pat!(Status {
+                rpm: approx_eq(1500.0),
+                current: approx_eq(1.5),
+                duty_cycle: approx_eq(0.5),
+            })))
+        )))
+    );
+
+    let reply = CanFrame::new(0x0801, &[42, 1, 36, 7, 208]).unwrap();
+    vesc.send_frame(&reply).unwrap();
+    let received = host.recv().unwrap();
+    assert_that!(
+        received,
+        some(pat!(Received::Reply(
+            eq(&42),
+            pat!(CommandReply::CustomAppData(eq(&[7, 208].as_slice())))
+        )))
+    );
+}
+
+#[test]
+#[ignore = "requires a vcan0 interface"]
+fn receive_pong_with_filter() {
+    let mut host = SocketCan::open("vcan0", 1, &[42]).unwrap();
+    let vesc = SocketCan::open("vcan0", 42, &[]).unwrap();
+
+    // Pongs are addressed to the host, and carry the sender's ID in the data.
+    let pong = CanFrame::new(0x1201, &[43, 0]).unwrap();
+    vesc.send_frame(&pong).unwrap();
+    assert_that!(host.recv().unwrap(), none());
+
+    let pong = CanFrame::new(0x1201, &[42, 0]).unwrap();
+    vesc.send_frame(&pong).unwrap();
+    let received = host.recv().unwrap();
+    assert_that!(
+        received,
+        some(pat!(Received::Message(
+            eq(&42),
+            pat!(Message::Pong(eq(&HwType::Vesc)))
+        )))
+    );
+}